@@ -1,5 +1,6 @@
 use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
 use disty_cli::{kde::KDE, parsing, stats::Stats};
+use rayon::slice::ParallelSliceMut;
 use std::hint::black_box;
 use std::io::Write as IoWrite;
 use tempfile::NamedTempFile;
@@ -13,6 +14,15 @@ fn generate_test_file(n: usize) -> NamedTempFile {
     file
 }
 
+fn generate_binary_file(n: usize) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 1..=n {
+        file.write_all(&(i as f64).to_le_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+    file
+}
+
 fn bench_parsing(c: &mut Criterion) {
     let mut group = c.benchmark_group("parsing");
 
@@ -26,7 +36,24 @@ fn bench_parsing(c: &mut Criterion) {
                 let temp_file = generate_test_file(size);
                 b.iter(|| {
                     let file = temp_file.reopen().unwrap();
-                    let data = parsing::read_file_mmap(&file, None);
+                    let data = parsing::read_file_mmap(&file, &parsing::ParseOptions::default());
+                    black_box(data)
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("read_file_mmap_binary", size),
+            &size,
+            |b, &size| {
+                let temp_file = generate_binary_file(size);
+                let opts = parsing::ParseOptions {
+                    binary: true,
+                    ..Default::default()
+                };
+                b.iter(|| {
+                    let file = temp_file.reopen().unwrap();
+                    let data = parsing::read_file_mmap(&file, &opts);
                     black_box(data)
                 });
             },
@@ -51,6 +78,52 @@ fn bench_stats(c: &mut Criterion) {
             });
         });
 
+        // The same already-sorted data, skipping the sort (--assume-sorted)
+        group.bench_with_input(
+            BenchmarkId::new("stats_from_sorted", size),
+            &data,
+            |b, data| {
+                b.iter(|| {
+                    let stats = Stats::from_sorted(black_box(data.clone()));
+                    black_box(stats)
+                });
+            },
+        );
+
+        // Unsorted input, so the sort is measured alongside the reductions
+        let shuffled: Vec<f64> = (0..size).map(|i| ((i * 7919) % size) as f64).collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("stats_new_unsorted", size),
+            &shuffled,
+            |b, data| {
+                b.iter(|| {
+                    let stats = Stats::new(black_box(data.clone()));
+                    black_box(stats)
+                });
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("sort_by", size), &shuffled, |b, data| {
+            b.iter(|| {
+                let mut data = black_box(data.clone());
+                data.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                black_box(data)
+            });
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("par_sort_unstable_by", size),
+            &shuffled,
+            |b, data| {
+                b.iter(|| {
+                    let mut data = black_box(data.clone());
+                    data.par_sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                    black_box(data)
+                });
+            },
+        );
+
         let stats = Stats::new(data.clone());
 
         group.bench_with_input(
@@ -114,6 +187,16 @@ fn bench_kde(c: &mut Criterion) {
                 });
             },
         );
+
+        // Same 160 points through the binned approximation used for plotting
+        group.bench_with_input(
+            BenchmarkId::new("kde_pdf_grid_160_points", size),
+            &kde,
+            |b, kde| {
+                let bounds = kde.bounds();
+                b.iter(|| black_box(kde.pdf_grid(black_box(bounds), 160)));
+            },
+        );
     }
 
     group.finish();
@@ -132,12 +215,26 @@ fn bench_full_pipeline(c: &mut Criterion) {
                 let temp_file = generate_test_file(size);
                 b.iter(|| {
                     let file = temp_file.reopen().unwrap();
-                    let data = parsing::read_file_mmap(&file, None);
+                    let data = parsing::read_file_mmap(&file, &parsing::ParseOptions::default());
                     let stats = Stats::new(data);
                     black_box(stats)
                 });
             },
         );
+
+        // The --count-only path: the same file, with no values kept or sorted
+        group.bench_with_input(
+            BenchmarkId::new("parse_and_total", size),
+            &size,
+            |b, &size| {
+                let temp_file = generate_test_file(size);
+                b.iter(|| {
+                    let file = temp_file.reopen().unwrap();
+                    let totals = parsing::total_file_mmap(&file, &parsing::ParseOptions::default());
+                    black_box(totals.sum())
+                });
+            },
+        );
     }
 
     // Only benchmark smaller sizes for full pipeline with KDE (it's expensive)
@@ -149,7 +246,7 @@ fn bench_full_pipeline(c: &mut Criterion) {
                 let temp_file = generate_test_file(size);
                 b.iter(|| {
                     let file = temp_file.reopen().unwrap();
-                    let data = parsing::read_file_mmap(&file, None);
+                    let data = parsing::read_file_mmap(&file, &parsing::ParseOptions::default());
                     let stats = Stats::new(data);
                     let kde = KDE::new(&stats.data);
                     // Evaluate PDF at one point to ensure KDE is fully used
@@ -1,4 +1,9 @@
-#[derive(Clone, Copy, clap::ValueEnum)]
+/// Decimal places shown when no precision is requested
+pub const DEFAULT_PRECISION: usize = 2;
+/// Significant figures targeted when precision is chosen automatically
+pub const AUTO_SIGNIFICANT_FIGURES: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
 pub enum Format {
     #[value(name = "float")]
     Float,
@@ -8,55 +13,206 @@ pub enum Format {
     Time,
     #[value(name = "bytes")]
     Bytes,
+    /// Bytes with decimal (SI) prefixes: KB, MB, GB...
+    #[value(name = "bytes-si")]
+    BytesSi,
+    /// Scientific notation, e.g. 1.23e-9
+    #[value(name = "sci")]
+    Scientific,
+    /// Bits with decimal prefixes: Kb, Mb, Gb...
+    #[value(name = "bits")]
+    Bits,
+    /// Per-second rate with k/M/G prefixes, e.g. 1.50M/s
+    #[value(name = "rate")]
+    Rate,
 }
 
 impl Format {
     pub fn format(&self, value: f64) -> String {
+        self.format_with(value, DEFAULT_PRECISION)
+    }
+
+    /// Formats with `precision` decimal places (digits after the point in scientific notation).
+    /// Hex and whole-byte values are integers and ignore it.
+    pub fn format_with(&self, value: f64, precision: usize) -> String {
         match self {
-            Format::Float => format!("{:.2}", value),
+            Format::Float => format!("{:.*}", precision, value),
+            // NaN would otherwise cast to 0 and print as a real-looking 0x0
+            Format::Hex if !value.is_finite() => value.to_string(),
             Format::Hex => format!("0x{:x}", value as u64),
-            Format::Time => format_duration(value),
-            Format::Bytes => format_bytes(value),
+            Format::Time => format_duration(value, precision),
+            Format::Bytes => format_bytes(value, precision),
+            Format::BytesSi => format_bytes_si(value, precision),
+            Format::Scientific => format_scientific(value, precision + 1),
+            Format::Bits => format_bits(value, precision),
+            Format::Rate => format_rate(value, precision),
+        }
+    }
+
+    /// Decimal places for `format_with` that show `value` to `significant` figures in the
+    /// unit it's displayed in (so 1234ns, shown as µs, gets 2 for "1.23µs")
+    pub fn auto_precision(&self, value: f64, significant: usize) -> usize {
+        match self {
+            Format::Float => significant_decimals(value, significant),
+            Format::Scientific => significant.max(1) - 1,
+            Format::Hex => 0,
+            _ => {
+                let (scale, _) = get_display_scale(value.abs(), *self);
+                significant_decimals(value / scale, significant)
+            }
         }
     }
+
+    /// Collapses variants that measure the same dimension (binary and SI bytes)
+    pub fn family(&self) -> Format {
+        match self {
+            Format::BytesSi => Format::Bytes,
+            other => *other,
+        }
+    }
+}
+
+/// Decimal places that show `value` to `significant` figures, from its order of magnitude:
+/// 3 figures is 5 decimals for 0.0000123 but none for 12345.6. Zero is treated as if it were
+/// between 1 and 10, like other values with no leading zeros.
+pub fn significant_decimals(value: f64, significant: usize) -> usize {
+    let magnitude = if value == 0.0 || !value.is_finite() {
+        0
+    } else {
+        value.abs().log10().floor() as i64
+    };
+    (significant as i64 - 1 - magnitude).max(0) as usize
 }
 
-pub fn format_duration(ns: f64) -> String {
+/// Formats in scientific notation with the given number of significant digits (at least 1)
+pub fn format_scientific(value: f64, significant: usize) -> String {
+    format!("{:.*e}", significant.max(1) - 1, value)
+}
+
+pub fn format_duration(ns: f64, precision: usize) -> String {
+    if !ns.is_finite() {
+        return ns.to_string();
+    }
+    if ns < 0.0 {
+        // Negative durations show up as derived values, e.g. a lower outlier fence
+        return format!("-{}", format_duration(-ns, precision));
+    }
     if ns < 1e3 {
-        format!("{:.2}ns", ns)
+        format!("{:.*}ns", precision, ns)
     } else if ns < 1e6 {
-        format!("{:.2}µs", ns / 1e3)
+        format!("{:.*}µs", precision, ns / 1e3)
     } else if ns < 1e9 {
-        format!("{:.2}ms", ns / 1e6)
+        format!("{:.*}ms", precision, ns / 1e6)
     } else if ns < 60e9 {
-        format!("{:.2}s", ns / 1e9)
+        format!("{:.*}s", precision, ns / 1e9)
     } else if ns < 3600e9 {
         let mins = (ns / 60e9).floor();
         let secs = (ns - mins * 60e9) / 1e9;
-        format!("{}m{:.2}s", mins as i64, secs)
+        format!("{}m{:.*}s", mins as i64, precision, secs)
     } else {
         let hours = (ns / 3600e9).floor();
         let mins = ((ns - hours * 3600e9) / 60e9).floor();
         let secs = (ns - hours * 3600e9 - mins * 60e9) / 1e9;
-        format!("{}h{}m{:.2}s", hours as i64, mins as i64, secs)
+        format!("{}h{}m{:.*}s", hours as i64, mins as i64, precision, secs)
     }
 }
 
-pub fn format_bytes(bytes: f64) -> String {
-    let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
-    let mut value = bytes;
-    let mut unit_idx = 0;
+/// Formats a byte count with binary prefixes (1KiB = 1024B). Whole bytes print without
+/// decimals, but a fraction of a byte (e.g. from --scale) keeps `precision` places.
+pub fn format_bytes(bytes: f64, precision: usize) -> String {
+    format_prefixed(
+        bytes,
+        precision,
+        1024.0,
+        &["B", "KiB", "MiB", "GiB", "TiB", "PiB"],
+    )
+}
+
+/// Like `format_bytes`, but with decimal (SI) prefixes: 1KB = 1000B
+pub fn format_bytes_si(bytes: f64, precision: usize) -> String {
+    format_prefixed(
+        bytes,
+        precision,
+        1000.0,
+        &["B", "KB", "MB", "GB", "TB", "PB"],
+    )
+}
+
+/// Formats a bit count with decimal prefixes, as network rates are usually quoted
+pub fn format_bits(bits: f64, precision: usize) -> String {
+    format_prefixed(bits, precision, 1000.0, &["b", "Kb", "Mb", "Gb", "Tb"])
+}
 
-    while value >= 1024.0 && unit_idx < units.len() - 1 {
-        value /= 1024.0;
+/// Formats `value` in the largest of `units`, each `base` times the one before, that keeps
+/// it at least 1. Whole counts of the smallest unit print without decimals.
+fn format_prefixed(value: f64, precision: usize, base: f64, units: &[&str]) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    if value < 0.0 {
+        return format!("-{}", format_prefixed(-value, precision, base, units));
+    }
+
+    let mut value = value;
+    let mut unit_idx = 0;
+    while value >= base && unit_idx < units.len() - 1 {
+        value /= base;
         unit_idx += 1;
     }
 
-    if unit_idx == 0 {
+    if unit_idx == 0 && (value == 0.0 || value >= 1.0) {
         format!("{:.0}{}", value, units[unit_idx])
     } else {
-        format!("{:.2}{}", value, units[unit_idx])
+        format!("{:.*}{}", precision, value, units[unit_idx])
+    }
+}
+
+/// Formats events per second (ops/sec, Hz) with metric prefixes, e.g. 1.5e6 as "1.50M/s"
+pub fn format_rate(per_second: f64, precision: usize) -> String {
+    let prefixes = ["", "k", "M", "G", "T"];
+    let mut value = per_second;
+    let mut prefix_idx = 0;
+
+    while value.abs() >= 1000.0 && prefix_idx < prefixes.len() - 1 {
+        value /= 1000.0;
+        prefix_idx += 1;
+    }
+
+    format!("{:.*}{}/s", precision, value, prefixes[prefix_idx])
+}
+
+/// Inserts thousands separators into the integer part of a formatted number,
+/// e.g. "-1001001.00" becomes "-1,001,001.00"
+pub fn group_thousands(formatted: &str) -> String {
+    let (sign, unsigned) = match formatted.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", formatted),
+    };
+    let int_len = unsigned
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(unsigned.len());
+    let (int_part, rest) = unsigned.split_at(int_len);
+
+    let mut grouped = String::with_capacity(formatted.len() + int_len / 3);
+    grouped.push_str(sign);
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_len - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
     }
+    grouped.push_str(rest);
+    grouped
+}
+
+/// Formats a value in a fixed unit rather than auto-scaling, e.g. 2e4ns as "0.02ms"
+pub fn format_fixed(value: f64, scale: f64, label: &str, precision: usize) -> String {
+    format!("{:.*}{}", precision, value / scale, label)
+}
+
+/// Formats a value as a multiple of a reference, e.g. 130ms against a 100ms target as "1.30×"
+pub fn format_relative(value: f64, reference: f64, precision: usize) -> String {
+    format!("{:.*}×", precision, value / reference)
 }
 
 /// Selects the largest unit where max_value remains >= 1 to avoid tiny decimals
@@ -91,8 +247,51 @@ pub fn get_display_scale(max_value: f64, format: Format) -> (f64, &'static str)
                 (1024.0_f64.powi(5), "PiB")
             }
         }
+        Format::BytesSi => {
+            // Same as above with decimal prefixes
+            if max_value < 1e3 {
+                (1.0, "B")
+            } else if max_value < 1e6 {
+                (1e3, "KB")
+            } else if max_value < 1e9 {
+                (1e6, "MB")
+            } else if max_value < 1e12 {
+                (1e9, "GB")
+            } else if max_value < 1e15 {
+                (1e12, "TB")
+            } else {
+                (1e15, "PB")
+            }
+        }
+        Format::Bits => {
+            if max_value < 1e3 {
+                (1.0, "b")
+            } else if max_value < 1e6 {
+                (1e3, "Kb")
+            } else if max_value < 1e9 {
+                (1e6, "Mb")
+            } else if max_value < 1e12 {
+                (1e9, "Gb")
+            } else {
+                (1e12, "Tb")
+            }
+        }
+        Format::Rate => {
+            if max_value < 1e3 {
+                (1.0, "/s")
+            } else if max_value < 1e6 {
+                (1e3, "k/s")
+            } else if max_value < 1e9 {
+                (1e6, "M/s")
+            } else if max_value < 1e12 {
+                (1e9, "G/s")
+            } else {
+                (1e12, "T/s")
+            }
+        }
         Format::Float => (1.0, ""),
         Format::Hex => (1.0, ""),
+        Format::Scientific => (1.0, ""),
     }
 }
 
@@ -102,82 +301,284 @@ mod tests {
 
     #[test]
     fn test_format_duration_nanoseconds() {
-        assert_eq!(format_duration(1.0), "1.00ns");
-        assert_eq!(format_duration(500.0), "500.00ns");
-        assert_eq!(format_duration(999.0), "999.00ns");
+        assert_eq!(format_duration(1.0, 2), "1.00ns");
+        assert_eq!(format_duration(500.0, 2), "500.00ns");
+        assert_eq!(format_duration(999.0, 2), "999.00ns");
     }
 
     #[test]
     fn test_format_duration_microseconds() {
-        assert_eq!(format_duration(1e3), "1.00µs");
-        assert_eq!(format_duration(5e3), "5.00µs");
-        assert_eq!(format_duration(500e3), "500.00µs");
+        assert_eq!(format_duration(1e3, 2), "1.00µs");
+        assert_eq!(format_duration(5e3, 2), "5.00µs");
+        assert_eq!(format_duration(500e3, 2), "500.00µs");
     }
 
     #[test]
     fn test_format_duration_milliseconds() {
-        assert_eq!(format_duration(1e6), "1.00ms");
-        assert_eq!(format_duration(5e6), "5.00ms");
-        assert_eq!(format_duration(500e6), "500.00ms");
+        assert_eq!(format_duration(1e6, 2), "1.00ms");
+        assert_eq!(format_duration(5e6, 2), "5.00ms");
+        assert_eq!(format_duration(500e6, 2), "500.00ms");
     }
 
     #[test]
     fn test_format_duration_seconds() {
-        assert_eq!(format_duration(1e9), "1.00s");
-        assert_eq!(format_duration(5e9), "5.00s");
-        assert_eq!(format_duration(30e9), "30.00s");
+        assert_eq!(format_duration(1e9, 2), "1.00s");
+        assert_eq!(format_duration(5e9, 2), "5.00s");
+        assert_eq!(format_duration(30e9, 2), "30.00s");
     }
 
     #[test]
     fn test_format_duration_minutes() {
-        assert_eq!(format_duration(60e9), "1m0.00s");
-        assert_eq!(format_duration(90e9), "1m30.00s");
-        assert_eq!(format_duration(150e9), "2m30.00s");
+        assert_eq!(format_duration(60e9, 2), "1m0.00s");
+        assert_eq!(format_duration(90e9, 2), "1m30.00s");
+        assert_eq!(format_duration(150e9, 2), "2m30.00s");
     }
 
     #[test]
     fn test_format_duration_hours() {
-        assert_eq!(format_duration(3600e9), "1h0m0.00s");
-        assert_eq!(format_duration(3661e9), "1h1m1.00s");
-        assert_eq!(format_duration(7384e9), "2h3m4.00s");
+        assert_eq!(format_duration(3600e9, 2), "1h0m0.00s");
+        assert_eq!(format_duration(3661e9, 2), "1h1m1.00s");
+        assert_eq!(format_duration(7384e9, 2), "2h3m4.00s");
+    }
+
+    #[test]
+    fn test_format_duration_negative() {
+        assert_eq!(format_duration(-500.0, 2), "-500.00ns");
+        assert_eq!(format_duration(-14.5e6, 2), "-14.50ms");
+    }
+
+    #[test]
+    fn test_format_duration_negative_each_band() {
+        assert_eq!(format_duration(-999.0, 2), "-999.00ns");
+        assert_eq!(format_duration(-1.5e3, 2), "-1.50µs");
+        assert_eq!(format_duration(-250e6, 1), "-250.0ms");
+        assert_eq!(format_duration(-2e9, 2), "-2.00s");
+        assert_eq!(format_duration(-90e9, 2), "-1m30.00s");
+        assert_eq!(format_duration(-3723e9, 0), "-1h2m3s");
     }
 
     #[test]
     fn test_format_bytes_bytes() {
-        assert_eq!(format_bytes(0.0), "0B");
-        assert_eq!(format_bytes(100.0), "100B");
-        assert_eq!(format_bytes(1023.0), "1023B");
+        assert_eq!(format_bytes(0.0, 2), "0B");
+        assert_eq!(format_bytes(100.0, 2), "100B");
+        assert_eq!(format_bytes(1023.0, 2), "1023B");
+    }
+
+    #[test]
+    fn test_format_bytes_negative() {
+        assert_eq!(format_bytes(-100.0, 2), "-100B");
+        assert_eq!(format_bytes(-1536.0, 2), "-1.50KiB");
+        assert_eq!(format_bytes(-5.0 * 1024.0 * 1024.0, 2), "-5.00MiB");
+        assert_eq!(format_bytes(-2.0 * 1024f64.powi(3), 1), "-2.0GiB");
+        assert_eq!(format_bytes(-1024f64.powi(4), 2), "-1.00TiB");
+        assert_eq!(format_bytes(-3.0 * 1024f64.powi(5), 2), "-3.00PiB");
+        assert_eq!(format_bytes_si(-2.5e6, 2), "-2.50MB");
+        assert_eq!(format_bits(-1.5e9, 2), "-1.50Gb");
+    }
+
+    #[test]
+    fn test_format_bytes_sub_byte() {
+        assert_eq!(format_bytes(0.5, 2), "0.50B");
+        assert_eq!(format_bytes(-0.25, 2), "-0.25B");
+        assert_eq!(format_bytes_si(0.125, 3), "0.125B");
+        assert_eq!(format_bits(0.5, 1), "0.5b");
+        assert_eq!(format_bytes(1.0, 2), "1B");
     }
 
     #[test]
     fn test_format_bytes_kibibytes() {
-        assert_eq!(format_bytes(1024.0), "1.00KiB");
-        assert_eq!(format_bytes(2048.0), "2.00KiB");
-        assert_eq!(format_bytes(1536.0), "1.50KiB");
+        assert_eq!(format_bytes(1024.0, 2), "1.00KiB");
+        assert_eq!(format_bytes(2048.0, 2), "2.00KiB");
+        assert_eq!(format_bytes(1536.0, 2), "1.50KiB");
     }
 
     #[test]
     fn test_format_bytes_mebibytes() {
-        assert_eq!(format_bytes(1024.0 * 1024.0), "1.00MiB");
-        assert_eq!(format_bytes(2.5 * 1024.0 * 1024.0), "2.50MiB");
+        assert_eq!(format_bytes(1024.0 * 1024.0, 2), "1.00MiB");
+        assert_eq!(format_bytes(2.5 * 1024.0 * 1024.0, 2), "2.50MiB");
     }
 
     #[test]
     fn test_format_bytes_gibibytes() {
-        assert_eq!(format_bytes(1024.0_f64.powi(3)), "1.00GiB");
-        assert_eq!(format_bytes(5.5 * 1024.0_f64.powi(3)), "5.50GiB");
+        assert_eq!(format_bytes(1024.0_f64.powi(3), 2), "1.00GiB");
+        assert_eq!(format_bytes(5.5 * 1024.0_f64.powi(3), 2), "5.50GiB");
     }
 
     #[test]
     fn test_format_bytes_tebibytes() {
-        assert_eq!(format_bytes(1024.0_f64.powi(4)), "1.00TiB");
-        assert_eq!(format_bytes(2.75 * 1024.0_f64.powi(4)), "2.75TiB");
+        assert_eq!(format_bytes(1024.0_f64.powi(4), 2), "1.00TiB");
+        assert_eq!(format_bytes(2.75 * 1024.0_f64.powi(4), 2), "2.75TiB");
     }
 
     #[test]
     fn test_format_bytes_pebibytes() {
-        assert_eq!(format_bytes(1024.0_f64.powi(5)), "1.00PiB");
-        assert_eq!(format_bytes(2.75 * 1024.0_f64.powi(5)), "2.75PiB");
+        assert_eq!(format_bytes(1024.0_f64.powi(5), 2), "1.00PiB");
+        assert_eq!(format_bytes(2.75 * 1024.0_f64.powi(5), 2), "2.75PiB");
+    }
+
+    #[test]
+    fn test_format_bytes_si() {
+        assert_eq!(format_bytes_si(0.0, 2), "0B");
+        assert_eq!(format_bytes_si(999.0, 2), "999B");
+        assert_eq!(format_bytes_si(1000.0, 2), "1.00KB");
+        assert_eq!(format_bytes_si(1024.0, 2), "1.02KB");
+        assert_eq!(format_bytes_si(2.5e6, 2), "2.50MB");
+        assert_eq!(format_bytes_si(1e9, 2), "1.00GB");
+        assert_eq!(format_bytes_si(1e12, 2), "1.00TB");
+        assert_eq!(format_bytes_si(3e15, 2), "3.00PB");
+    }
+
+    #[test]
+    fn test_format_bytes_si_vs_binary() {
+        assert_eq!(format_bytes_si(1000.0, 2), "1.00KB");
+        assert_eq!(format_bytes(1000.0, 2), "1000B");
+        assert_eq!(format_bytes_si(1024.0 * 1024.0, 2), "1.05MB");
+        assert_eq!(format_bytes(1024.0 * 1024.0, 2), "1.00MiB");
+    }
+
+    #[test]
+    fn test_format_bits() {
+        assert_eq!(format_bits(0.0, 2), "0b");
+        assert_eq!(format_bits(999.0, 2), "999b");
+        assert_eq!(format_bits(1000.0, 2), "1.00Kb");
+        assert_eq!(format_bits(2.5e6, 2), "2.50Mb");
+        assert_eq!(format_bits(1e9, 2), "1.00Gb");
+        assert_eq!(format_bits(4e15, 2), "4000.00Tb");
+        assert_eq!(Format::Bits.format(1e8), "100.00Mb");
+    }
+
+    #[test]
+    fn test_format_rate_sub_one() {
+        assert_eq!(format_rate(0.5, 2), "0.50/s");
+        assert_eq!(format_rate(0.0, 2), "0.00/s");
+    }
+
+    #[test]
+    fn test_format_rate_thousands() {
+        assert_eq!(format_rate(999.0, 2), "999.00/s");
+        assert_eq!(format_rate(1500.0, 2), "1.50k/s");
+        assert_eq!(format_rate(250e3, 1), "250.0k/s");
+    }
+
+    #[test]
+    fn test_format_rate_millions() {
+        assert_eq!(format_rate(1.5e6, 2), "1.50M/s");
+        assert_eq!(format_rate(3e9, 2), "3.00G/s");
+        assert_eq!(Format::Rate.format(2e6), "2.00M/s");
+    }
+
+    #[test]
+    fn test_format_fixed() {
+        assert_eq!(format_fixed(2e4, 1e6, "ms", 2), "0.02ms");
+        assert_eq!(format_fixed(2e9, 1e6, "ms", 2), "2000.00ms");
+        assert_eq!(format_fixed(1536.0, 1024.0, "KiB", 2), "1.50KiB");
+    }
+
+    #[test]
+    fn test_significant_decimals() {
+        let cases = [
+            (0.0000123, 7),
+            (0.000999, 6),
+            (0.5, 3),
+            (0.0, 2),
+            (1.0, 2),
+            (9.5, 2),
+            (12.0, 1),
+            (-123.4, 0),
+            (12345.6, 0),
+            (1e12, 0),
+            (f64::NAN, 2),
+        ];
+        for (value, decimals) in cases {
+            assert_eq!(significant_decimals(value, 3), decimals, "{value}");
+        }
+        assert_eq!(significant_decimals(0.0000123, 1), 5);
+    }
+
+    #[test]
+    fn test_auto_precision_across_magnitudes() {
+        let auto =
+            |format: Format, value: f64| format.format_with(value, format.auto_precision(value, 3));
+        assert_eq!(auto(Format::Float, 0.0000123), "0.0000123");
+        assert_eq!(auto(Format::Float, 0.0456), "0.0456");
+        assert_eq!(auto(Format::Float, 7.891), "7.89");
+        assert_eq!(auto(Format::Float, 12345.6), "12346");
+        // Unit formats count figures in the unit the value is shown in
+        assert_eq!(auto(Format::Time, 1234.0), "1.23µs");
+        assert_eq!(auto(Format::Time, 56_789_000.0), "56.8ms");
+        assert_eq!(auto(Format::Time, 0.25), "0.250ns");
+        assert_eq!(auto(Format::BytesSi, 987_654_321.0), "988MB");
+        assert_eq!(auto(Format::Scientific, 0.0000123), "1.23e-5");
+    }
+
+    #[test]
+    fn test_format_non_finite() {
+        assert_eq!(format_duration(f64::NAN, 2), "NaN");
+        assert_eq!(format_duration(f64::NEG_INFINITY, 2), "-inf");
+        assert_eq!(format_bytes(f64::NAN, 2), "NaN");
+        assert_eq!(format_bytes_si(f64::INFINITY, 2), "inf");
+        assert_eq!(format_bits(f64::NAN, 2), "NaN");
+        assert_eq!(Format::Hex.format(f64::NAN), "NaN");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        assert_eq!(format_relative(130e6, 100e6, 2), "1.30×");
+        assert_eq!(format_relative(5.0, 10.0, 1), "0.5×");
+        assert_eq!(format_relative(-3.0, 2.0, 2), "-1.50×");
+    }
+
+    #[test]
+    fn test_format_with_precision() {
+        assert_eq!(Format::Float.format_with(1.23456, 4), "1.2346");
+        assert_eq!(Format::Float.format_with(1.5, 0), "2");
+        assert_eq!(Format::Time.format_with(1234.5678, 4), "1.2346µs");
+        assert_eq!(Format::Time.format_with(90.5e9, 0), "1m30s");
+        assert_eq!(Format::Bytes.format_with(1536.0, 3), "1.500KiB");
+        assert_eq!(Format::BytesSi.format_with(1500.0, 1), "1.5KB");
+        assert_eq!(Format::Scientific.format_with(123456.0, 4), "1.2346e5");
+        assert_eq!(Format::Hex.format_with(255.0, 4), "0xff");
+    }
+
+    #[test]
+    fn test_format_default_precision() {
+        assert_eq!(
+            Format::Time.format(1234.5678),
+            Format::Time.format_with(1234.5678, DEFAULT_PRECISION)
+        );
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        let cases = [
+            ("1.00", "1.00"),
+            ("12.00", "12.00"),
+            ("123.00", "123.00"),
+            ("1234.00", "1,234.00"),
+            ("12345.00", "12,345.00"),
+            ("123456.00", "123,456.00"),
+            ("1234567.00", "1,234,567.00"),
+            ("12345678.00", "12,345,678.00"),
+            ("123456789.00", "123,456,789.00"),
+            ("1234567890.00", "1,234,567,890.00"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(group_thousands(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_group_thousands_negative() {
+        assert_eq!(group_thousands("-1001001.00"), "-1,001,001.00");
+        assert_eq!(group_thousands("-999.99"), "-999.99");
+        assert_eq!(group_thousands("-1000"), "-1,000");
+    }
+
+    #[test]
+    fn test_group_thousands_no_fraction() {
+        assert_eq!(group_thousands("1001001"), "1,001,001");
+        assert_eq!(group_thousands("0"), "0");
+        assert_eq!(group_thousands("0.123456"), "0.123456");
     }
 
     #[test]
@@ -187,6 +588,27 @@ mod tests {
         assert_eq!(Format::Float.format(1000.0), "1000.00");
     }
 
+    #[test]
+    fn test_format_scientific() {
+        assert_eq!(Format::Scientific.format(1.234e-9), "1.23e-9");
+        assert_eq!(Format::Scientific.format(123456789.0), "1.23e8");
+        assert_eq!(Format::Scientific.format(-4.5e3), "-4.50e3");
+        assert_eq!(Format::Scientific.format(0.0), "0.00e0");
+    }
+
+    #[test]
+    fn test_format_scientific_subnormal() {
+        assert_eq!(format_scientific(5e-324, 3), "4.94e-324");
+        assert_eq!(format_scientific(f64::MIN_POSITIVE / 2.0, 2), "1.1e-308");
+    }
+
+    #[test]
+    fn test_format_scientific_significant_digits() {
+        assert_eq!(format_scientific(123456.0, 1), "1e5");
+        assert_eq!(format_scientific(123456.0, 5), "1.2346e5");
+        assert_eq!(format_scientific(123456.0, 0), "1e5");
+    }
+
     #[test]
     fn test_format_hex() {
         assert_eq!(Format::Hex.format(255.0), "0xff");
@@ -255,6 +677,42 @@ mod tests {
         assert_eq!(unit, "MiB");
     }
 
+    #[test]
+    fn test_format_bytes_si_format() {
+        assert_eq!(Format::BytesSi.format(1000.0), "1.00KB");
+        assert_eq!(Format::BytesSi.format(2.5e9), "2.50GB");
+    }
+
+    #[test]
+    fn test_format_family() {
+        assert!(Format::BytesSi.family() == Format::Bytes);
+        assert!(Format::Bytes.family() == Format::Bytes);
+        assert!(Format::Time.family() == Format::Time);
+    }
+
+    #[test]
+    fn test_get_display_scale_bytes_si() {
+        assert_eq!(get_display_scale(512.0, Format::BytesSi), (1.0, "B"));
+        assert_eq!(get_display_scale(2000.0, Format::BytesSi), (1e3, "KB"));
+        assert_eq!(get_display_scale(5e6, Format::BytesSi), (1e6, "MB"));
+        assert_eq!(get_display_scale(5e9, Format::BytesSi), (1e9, "GB"));
+        assert_eq!(get_display_scale(5e15, Format::BytesSi), (1e15, "PB"));
+    }
+
+    #[test]
+    fn test_get_display_scale_bits() {
+        assert_eq!(get_display_scale(512.0, Format::Bits), (1.0, "b"));
+        assert_eq!(get_display_scale(2000.0, Format::Bits), (1e3, "Kb"));
+        assert_eq!(get_display_scale(5e9, Format::Bits), (1e9, "Gb"));
+        assert_eq!(get_display_scale(5e15, Format::Bits), (1e12, "Tb"));
+    }
+
+    #[test]
+    fn test_get_display_scale_rate() {
+        assert_eq!(get_display_scale(10.0, Format::Rate), (1.0, "/s"));
+        assert_eq!(get_display_scale(2e6, Format::Rate), (1e6, "M/s"));
+    }
+
     #[test]
     fn test_get_display_scale_float() {
         let (scale, unit) = get_display_scale(1000.0, Format::Float);
@@ -262,6 +720,13 @@ mod tests {
         assert_eq!(unit, "");
     }
 
+    #[test]
+    fn test_get_display_scale_scientific() {
+        let (scale, unit) = get_display_scale(1e12, Format::Scientific);
+        assert_eq!(scale, 1.0);
+        assert_eq!(unit, "");
+    }
+
     #[test]
     fn test_get_display_scale_hex() {
         let (scale, unit) = get_display_scale(255.0, Format::Hex);
@@ -1,25 +1,141 @@
+use rayon::prelude::*;
+
+/// How the plotted y values are expressed
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum YAxis {
+    /// Raw probability density (unlabeled)
+    #[value(name = "density")]
+    Density,
+    /// Density relative to the peak, from 0 to 1
+    #[value(name = "normalized")]
+    Normalized,
+    /// Approximate number of values per sample step (density × n × step width)
+    #[value(name = "count")]
+    Count,
+}
+
+impl YAxis {
+    /// Rescales evenly spaced (x, density) samples in place
+    pub fn rescale(&self, points: &mut [(f64, f64)], n: usize) {
+        match self {
+            YAxis::Density => {}
+            YAxis::Normalized => {
+                let peak = points.iter().map(|&(_, y)| y).fold(0.0, f64::max);
+                if peak > 0.0 {
+                    for point in points.iter_mut() {
+                        point.1 /= peak;
+                    }
+                }
+            }
+            YAxis::Count => {
+                let step = match (points.first(), points.last()) {
+                    (Some(first), Some(last)) if points.len() > 1 => {
+                        (last.0 - first.0) / (points.len() - 1) as f64
+                    }
+                    _ => 0.0,
+                };
+                for point in points.iter_mut() {
+                    point.1 *= n as f64 * step;
+                }
+            }
+        }
+    }
+}
+
+/// Bandwidth for degenerate (zero-spread) data, relative to the values' magnitude
+const MIN_RELATIVE_BANDWIDTH: f64 = 1e-3;
+
+/// Bins per bandwidth for `pdf_grid`'s linear binning; finer bins trade speed for accuracy
+const BINS_PER_BANDWIDTH: f64 = 8.0;
+
+/// Upper bound on `pdf_grid`'s bin count, so long-tailed data can't allocate without limit
+const MAX_BINS: usize = 1 << 18;
+
+/// Population standard deviation, the spread in Silverman's rule
+fn std_dev(data: &[f64]) -> f64 {
+    let n = data.len() as f64;
+    let mean = data.iter().sum::<f64>() / n;
+    let variance = data.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    variance.sqrt()
+}
+
 /// Simple Gaussian Kernel Density Estimator
-/// TODO make this even faster by porting the fast-kde paper cited at https://github.com/uwdata/fast-kde
 #[allow(clippy::upper_case_acronyms)]
 pub struct KDE<'a> {
     data: &'a [f64],
     bandwidth: f64,
+    /// Per-point bandwidths for the adaptive estimator (`bandwidth` is their maximum)
+    local_bandwidths: Option<Vec<f64>>,
 }
 
 impl<'a> KDE<'a> {
     /// Create a KDE with automatic bandwidth selection (Silverman's rule)
     /// Assumes data is already sorted
     pub fn new(data: &'a [f64]) -> Self {
-        let n = data.len() as f64;
+        Self::silverman(data, std_dev(data))
+    }
 
-        let mean = data.iter().sum::<f64>() / n;
-        let variance = data.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
-        let std_dev = variance.sqrt();
+    /// Like `new`, but with Silverman's outlier-resistant spread, min(σ, IQR / 1.34): a few
+    /// extreme values inflate σ and oversmooth the bulk of the data, but barely move the
+    /// IQR. Falls back to σ when the IQR is zero (over half the values tied).
+    pub fn robust(data: &'a [f64], iqr: f64) -> Self {
+        let std_dev = std_dev(data);
+        let spread = if iqr > 0.0 {
+            std_dev.min(iqr / 1.34)
+        } else {
+            std_dev
+        };
+        Self::silverman(data, spread)
+    }
+
+    fn silverman(data: &'a [f64], spread: f64) -> Self {
+        let n = data.len() as f64;
 
         // Silverman's rule of thumb: h ≈ 1.06 * σ * n^(-1/5)
-        let bandwidth = 1.06 * std_dev * n.powf(-0.2);
+        let mut bandwidth = 1.06 * spread * n.powf(-0.2);
+
+        // Constant data has no spread, and a zero bandwidth would make pdf() divide by zero.
+        // Fall back to a narrow spike scaled to the values' magnitude.
+        if !(bandwidth.is_finite() && bandwidth > 0.0) {
+            let magnitude = data.first().map_or(0.0, |x| x.abs());
+            bandwidth = (magnitude * MIN_RELATIVE_BANDWIDTH).max(MIN_RELATIVE_BANDWIDTH);
+        }
+
+        KDE {
+            data,
+            bandwidth,
+            local_bandwidths: None,
+        }
+    }
 
-        KDE { data, bandwidth }
+    /// Create an adaptive KDE using Abramson's rule: each point's bandwidth is the fixed
+    /// bandwidth scaled by (pilot density / geometric mean density)^(-1/2), so dense regions
+    /// get sharper kernels and sparse tails wider ones.
+    /// Costs an extra fixed-bandwidth pass (one pdf() per data point) to build the pilot, and
+    /// each pdf() then sums over a window as wide as the largest local bandwidth.
+    pub fn adaptive(data: &'a [f64]) -> Self {
+        KDE::new(data).into_adaptive()
+    }
+
+    /// An adaptive KDE (see `adaptive`) using this one's fixed bandwidth for the pilot
+    pub fn into_adaptive(self) -> Self {
+        let pilot = &self;
+        let data = self.data;
+        let pilot_density: Vec<f64> = data.par_iter().map(|&x| pilot.pdf(x)).collect();
+
+        let n = data.len() as f64;
+        let geo_mean = (pilot_density.iter().map(|f| f.ln()).sum::<f64>() / n).exp();
+        let local: Vec<f64> = pilot_density
+            .iter()
+            .map(|f| pilot.bandwidth * (f / geo_mean).powf(-0.5))
+            .collect();
+        let bandwidth = local.iter().copied().fold(pilot.bandwidth, f64::max);
+
+        KDE {
+            data,
+            bandwidth,
+            local_bandwidths: Some(local),
+        }
     }
 
     /// Probability density at x
@@ -37,6 +153,15 @@ impl<'a> KDE<'a> {
         let start_idx = self.data.partition_point(|&xi| xi < lower);
         let end_idx = self.data.partition_point(|&xi| xi <= upper);
 
+        if let Some(local) = &self.local_bandwidths {
+            let sum: f64 = self.data[start_idx..end_idx]
+                .iter()
+                .zip(&local[start_idx..end_idx])
+                .map(|(&xi, &hi)| gaussian_kernel((x - xi) / hi) / hi)
+                .sum();
+            return sum / n;
+        }
+
         let sum: f64 = self.data[start_idx..end_idx]
             .iter()
             .map(|&xi| gaussian_kernel((x - xi) / h))
@@ -45,11 +170,122 @@ impl<'a> KDE<'a> {
         sum / (n * h)
     }
 
+    /// Evaluates the PDF at `count` evenly spaced points across [min_x, max_x], in parallel.
+    /// This mimics what textplots does internally for Shape::Continuous,
+    /// but parallelizes the expensive pdf() evaluations
+    pub fn sample(&self, min_x: f64, max_x: f64, count: usize) -> Vec<(f64, f64)> {
+        let steps = count.saturating_sub(1).max(1) as f64;
+        (0..count)
+            .into_par_iter()
+            .map(|i| {
+                // Map pixel coordinate to data coordinate (inv_linear)
+                let x = min_x + (max_x - min_x) * (i as f64 / steps);
+                (x, self.pdf(x))
+            })
+            .collect()
+    }
+
+    /// Approximate PDF at `width` evenly spaced points across `bounds`, using the linear binning
+    /// and direct binned convolution from the fast-kde paper (https://github.com/uwdata/fast-kde).
+    /// The data is spread onto a grid of bins a fraction of a bandwidth wide in one O(n) pass,
+    /// then each output point sums only the bins within 4 bandwidths, so the cost no longer
+    /// grows with n·width. Only data within 4 bandwidths of `bounds` is binned, so a far
+    /// outlier doesn't stretch the grid. Adaptive estimators, and windows too wide to bin
+    /// finely within `MAX_BINS`, fall back to exact `sample`.
+    pub fn pdf_grid(&self, bounds: (f64, f64), width: usize) -> Vec<(f64, f64)> {
+        let (min_x, max_x) = bounds;
+        if self.data.is_empty() || self.local_bandwidths.is_some() {
+            return self.sample(min_x, max_x, width);
+        }
+
+        let h = self.bandwidth;
+        let cutoff = 4.0 * h;
+        let start = self.data.partition_point(|&x| x < min_x - cutoff);
+        let end = self.data.partition_point(|&x| x <= max_x + cutoff);
+        let steps = width.saturating_sub(1).max(1) as f64;
+        let grid_x = |i: usize| min_x + (max_x - min_x) * (i as f64 / steps);
+        let nearby = &self.data[start..end];
+        let (Some(&min), Some(&max)) = (nearby.first(), nearby.last()) else {
+            return (0..width).map(|i| (grid_x(i), 0.0)).collect();
+        };
+
+        let bins = ((max - min) / h * BINS_PER_BANDWIDTH).ceil() + 2.0;
+        if bins > MAX_BINS as f64 {
+            return self.sample(min_x, max_x, width);
+        }
+        let bins = bins as usize;
+        let spacing = ((max - min) / (bins - 1) as f64).max(f64::MIN_POSITIVE);
+
+        // Linear binning: each value splits its unit weight between the two nearest bins
+        let mut weights = vec![0.0; bins];
+        for &x in nearby {
+            let pos = (x - min) / spacing;
+            let i = (pos.floor() as usize).min(bins - 2);
+            let frac = pos - i as f64;
+            weights[i] += 1.0 - frac;
+            weights[i + 1] += frac;
+        }
+
+        let norm = self.data.len() as f64 * h;
+        (0..width)
+            .into_par_iter()
+            .map(|i| {
+                let x = grid_x(i);
+                let lo = ((x - cutoff - min) / spacing).ceil().max(0.0) as usize;
+                let hi = (((x + cutoff - min) / spacing).floor().max(-1.0) + 1.0) as usize;
+                // Folded from +0.0: an empty f64 sum is -0.0, which would print as "-0"
+                let sum = (lo..hi.min(bins)).fold(0.0, |sum, j| {
+                    sum + weights[j] * gaussian_kernel((x - min - j as f64 * spacing) / h)
+                });
+                (x, sum / norm)
+            })
+            .collect()
+    }
+
+    /// Estimates the mode as the x with the highest density over `resolution` evenly spaced
+    /// points across `bounds()`. For multimodal data this is the global peak; exact ties
+    /// resolve to the lowest x. Accuracy is limited to the grid spacing. Uses the binned
+    /// `pdf_grid`, since the exact `sample` costs n·resolution kernel evaluations.
+    pub fn mode(&self, resolution: usize) -> f64 {
+        self.pdf_grid(self.bounds(), resolution)
+            .into_iter()
+            .fold((f64::NAN, f64::NEG_INFINITY), |best, (x, y)| {
+                if y > best.1 { (x, y) } else { best }
+            })
+            .0
+    }
+
+    /// Differential entropy of the estimated density in nats, −∫ p(x)·ln p(x) dx, integrated
+    /// with the trapezoid rule over `resolution` points. The grid spans the data plus four
+    /// bandwidths either side (wider than `bounds()`, which can clip the tails at 0), so
+    /// nearly all of the density's mass is covered. Smoothing spreads the density, so larger
+    /// bandwidths give larger estimates than the data's true entropy.
+    pub fn entropy(&self, resolution: usize) -> f64 {
+        let (Some(&min), Some(&max)) = (self.data.first(), self.data.last()) else {
+            return f64::NAN;
+        };
+        let (lower, upper) = (min - 4.0 * self.bandwidth, max + 4.0 * self.bandwidth);
+        let samples = self.sample(lower, upper, resolution.max(2));
+        let step = (upper - lower) / (samples.len() - 1) as f64;
+
+        let integrand: Vec<f64> = samples
+            .iter()
+            .map(|&(_, p)| if p > 0.0 { -p * p.ln() } else { 0.0 })
+            .collect();
+        let interior: f64 = integrand[1..integrand.len() - 1].iter().sum();
+        step * (interior + (integrand[0] + integrand[integrand.len() - 1]) / 2.0)
+    }
+
     /// Get bounds for plotting (data range + 10% padding)
     pub fn bounds(&self) -> (f64, f64) {
         let min = self.data.first().copied().unwrap_or(0.0);
         let max = self.data.last().copied().unwrap_or(1.0);
-        let padding = (max - min) * 0.1;
+        // A single point still gets a visible window around its spike
+        let padding = if max > min {
+            (max - min) * 0.1
+        } else {
+            4.0 * self.bandwidth
+        };
 
         // Clamp lower bound to 0 if all data is non-negative
         let lower = if min >= 0.0 {
@@ -72,6 +308,7 @@ fn gaussian_kernel(u: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sorting::sort_floats;
 
     #[test]
     fn test_gaussian_kernel_at_zero() {
@@ -198,6 +435,245 @@ mod tests {
         assert!((kde.bandwidth - expected_bandwidth).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_kde_robust_bandwidth_resists_outlier() {
+        // A normal-ish bulk around 0 and one value far out, which inflates σ
+        let mut data: Vec<f64> = (0..999).map(|i| (i as f64 - 499.0) / 100.0).collect();
+        data.push(1e4);
+        let iqr = crate::stats::Stats::new(data.clone()).iqr();
+
+        let classic = KDE::new(&data);
+        let robust = KDE::robust(&data, iqr);
+        assert!(robust.bandwidth < classic.bandwidth / 10.0);
+        // Less flattened: the bulk's density is much closer to its true height of ~0.1
+        assert!(robust.pdf(0.0) > 0.09, "{}", robust.pdf(0.0));
+        assert!(classic.pdf(0.0) < 0.05, "{}", classic.pdf(0.0));
+
+        // Without outliers σ is the smaller spread, so nothing changes
+        let uniform = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(
+            KDE::robust(&uniform, 2.0).bandwidth,
+            KDE::new(&uniform).bandwidth
+        );
+        // Nor when the IQR is zero
+        let tied = [1.0, 1.0, 1.0, 1.0, 9.0];
+        assert_eq!(KDE::robust(&tied, 0.0).bandwidth, KDE::new(&tied).bandwidth);
+    }
+
+    #[test]
+    fn test_kde_sample_spans_bounds() {
+        let data = vec![1.0, 2.0, 3.0];
+        let kde = KDE::new(&data);
+        let points = kde.sample(0.0, 4.0, 5);
+
+        let xs: Vec<f64> = points.iter().map(|&(x, _)| x).collect();
+        assert_eq!(xs, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(points[2].1, kde.pdf(2.0));
+    }
+
+    #[test]
+    fn test_y_axis_normalized_peak_is_one() {
+        let data = vec![1.0, 1.1, 1.2, 5.0, 5.1, 5.2, 5.3];
+        let kde = KDE::new(&data);
+        let (min_x, max_x) = kde.bounds();
+        let mut points = kde.sample(min_x, max_x, 160);
+        YAxis::Normalized.rescale(&mut points, data.len());
+
+        let peak = points.iter().map(|&(_, y)| y).fold(0.0, f64::max);
+        assert_eq!(peak, 1.0);
+        assert!(points.iter().all(|&(_, y)| (0.0..=1.0).contains(&y)));
+    }
+
+    #[test]
+    fn test_y_axis_count_sums_to_n() {
+        let data: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let kde = KDE::new(&data);
+        let mut points = kde.sample(-50.0, 150.0, 400);
+        YAxis::Count.rescale(&mut points, data.len());
+
+        // Riemann sum of density × n over the whole support approximates n
+        let total: f64 = points.iter().map(|&(_, y)| y).sum();
+        assert!((total - 100.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_y_axis_density_unchanged() {
+        let mut points = vec![(0.0, 0.25), (1.0, 0.5)];
+        YAxis::Density.rescale(&mut points, 10);
+        assert_eq!(points, vec![(0.0, 0.25), (1.0, 0.5)]);
+    }
+
+    #[test]
+    fn test_kde_mode_unimodal() {
+        let data = vec![1.0, 2.0, 2.0, 3.0, 3.0, 3.0, 4.0, 4.0, 5.0];
+        let kde = KDE::new(&data);
+        assert!((kde.mode(1000) - 3.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_kde_mode_bimodal_global_peak() {
+        // The cluster near 10 has more mass, so it holds the global peak
+        let data = vec![1.0, 1.1, 9.9, 10.0, 10.0, 10.1];
+        let kde = KDE::new(&data);
+        let mode = kde.mode(1000);
+        assert!((mode - 10.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_kde_mode_matches_exact_sampling() {
+        // Skewed (lognormal-ish) data, so the peak sits away from the middle of the bounds
+        let data: Vec<f64> = (1..5000)
+            .map(|i| crate::stats::inverse_normal_cdf(i as f64 / 5000.0).exp())
+            .collect();
+        let kde = KDE::new(&data);
+        let (min_x, max_x) = kde.bounds();
+        let exact = kde
+            .sample(min_x, max_x, 1000)
+            .into_iter()
+            .fold((f64::NAN, f64::NEG_INFINITY), |best, (x, y)| {
+                if y > best.1 { (x, y) } else { best }
+            })
+            .0;
+        let step = (max_x - min_x) / 999.0;
+        assert!((kde.mode(1000) - exact).abs() <= 2.0 * step);
+    }
+
+    #[test]
+    fn test_kde_entropy_uniform() {
+        // Uniform on [0, 1] has entropy ln(1) = 0. Smoothing with Silverman's bandwidth
+        // (about 0.08 here) blurs the edges and adds roughly 0.14 nats.
+        let data: Vec<f64> = (0..=1000).map(|i| i as f64 / 1000.0).collect();
+        let entropy = KDE::new(&data).entropy(2000);
+        assert!(entropy > 0.0 && entropy < 0.2);
+
+        // Stretching by 10 adds exactly ln(10), since the bandwidth scales with the data
+        let stretched: Vec<f64> = data.iter().map(|x| x * 10.0).collect();
+        let stretched_entropy = KDE::new(&stretched).entropy(2000);
+        assert!((stretched_entropy - entropy - 10f64.ln()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_kde_entropy_normal() {
+        // A standard normal has entropy ½·ln(2πe) ≈ 1.419
+        let data: Vec<f64> = (1..2000)
+            .map(|i| crate::stats::inverse_normal_cdf(i as f64 / 2000.0))
+            .collect();
+        let entropy = KDE::new(&data).entropy(2000);
+        assert!((entropy - 1.419).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_kde_constant_data() {
+        let data = vec![42.0; 10];
+        let kde = KDE::new(&data);
+
+        assert!(kde.bandwidth > 0.0);
+        assert!(kde.pdf(42.0).is_finite() && kde.pdf(42.0) > 0.0);
+        assert!(kde.pdf(43.0).is_finite());
+
+        let (lower, upper) = kde.bounds();
+        assert!(lower < 42.0 && 42.0 < upper);
+        assert!((kde.mode(1000) - 42.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_kde_constant_zero() {
+        let data = vec![0.0; 3];
+        let kde = KDE::new(&data);
+        assert!(kde.pdf(0.0).is_finite());
+        assert!(kde.sample(-1.0, 1.0, 5).iter().all(|(_, y)| y.is_finite()));
+    }
+
+    /// Integrates the pdf with the trapezoid rule over a wide window
+    fn total_mass(kde: &KDE, lower: f64, upper: f64) -> f64 {
+        let samples = kde.sample(lower, upper, 4001);
+        let step = (upper - lower) / 4000.0;
+        samples.iter().map(|&(_, y)| y).sum::<f64>() * step
+    }
+
+    #[test]
+    fn test_adaptive_kde_integrates_to_one() {
+        let data = vec![1.0, 1.1, 1.2, 1.3, 1.4, 2.0, 5.0, 12.0];
+        let kde = KDE::adaptive(&data);
+        assert!((total_mass(&kde, -20.0, 40.0) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_adaptive_kde_sharper_in_dense_region() {
+        // A tight cluster plus a sparse tail: the adaptive estimate peaks higher in the
+        // cluster and reaches further out around the isolated tail point
+        let mut data: Vec<f64> = (0..50).map(|i| 10.0 + i as f64 * 0.01).collect();
+        data.push(30.0);
+        let fixed = KDE::new(&data);
+        let adaptive = KDE::adaptive(&data);
+
+        assert!(adaptive.pdf(10.25) > fixed.pdf(10.25));
+        assert!(adaptive.pdf(40.0) > fixed.pdf(40.0));
+    }
+
+    #[test]
+    fn test_pdf_grid_matches_exact_pdf() {
+        // Skewed and bimodal, so both dense and sparse regions are exercised
+        let mut data: Vec<f64> = (0..5000)
+            .map(|i| {
+                let u = (i as f64 + 0.5) / 5000.0;
+                (u * 10.0).powi(2) + if i % 3 == 0 { 40.0 } else { 0.0 }
+            })
+            .collect();
+        sort_floats(&mut data);
+        let kde = KDE::new(&data);
+        let bounds = kde.bounds();
+
+        let exact = kde.sample(bounds.0, bounds.1, 160);
+        let approx = kde.pdf_grid(bounds, 160);
+        let peak = exact.iter().map(|&(_, y)| y).fold(0.0, f64::max);
+        for ((xe, ye), (xa, ya)) in exact.iter().zip(&approx) {
+            assert_eq!(xe, xa);
+            assert!((ye - ya).abs() < 1e-3 * peak, "x={xe}: {ye} vs {ya}");
+        }
+    }
+
+    #[test]
+    fn test_pdf_grid_outside_data_is_zero() {
+        let data = vec![1.0, 2.0, 3.0];
+        let kde = KDE::new(&data);
+        let points = kde.pdf_grid((100.0, 200.0), 10);
+        assert_eq!(points.len(), 10);
+        assert!(points.iter().all(|&(_, y)| y == 0.0));
+    }
+
+    #[test]
+    fn test_pdf_grid_far_outlier() {
+        // 20k uniform values in [0, 1] and one far outlier, which would stretch a grid
+        // over the whole data range to bins far wider than the bandwidth
+        let mut data: Vec<f64> = (0..20_000).map(|i| (i as f64 + 0.5) / 20_000.0).collect();
+        data.push(1e7);
+        let kde = KDE::robust(&data, 0.5);
+
+        let exact = kde.sample(0.0, 1.0, 160);
+        let approx = kde.pdf_grid((0.0, 1.0), 160);
+        let peak = exact.iter().map(|&(_, y)| y).fold(0.0, f64::max);
+        for ((xe, ye), (_, ya)) in exact.iter().zip(&approx) {
+            assert!((ye - ya).abs() < 1e-3 * peak, "x={xe}: {ye} vs {ya}");
+        }
+        assert!(approx[80].1 > 0.9);
+
+        // No negative zeros far from the data either
+        let empty = kde.pdf_grid((2.0, 3.0), 10);
+        assert!(empty.iter().all(|&(_, y)| y.is_sign_positive()));
+    }
+
+    #[test]
+    fn test_pdf_grid_constant_data() {
+        let data = vec![5.0; 100];
+        let kde = KDE::new(&data);
+        let points = kde.pdf_grid(kde.bounds(), 81);
+        let exact = kde.sample(kde.bounds().0, kde.bounds().1, 81);
+        for ((_, ye), (_, ya)) in exact.iter().zip(&points) {
+            assert!((ye - ya).abs() < 1e-6 * ye.max(1.0));
+        }
+    }
+
     #[test]
     fn test_kde_pdf_bimodal() {
         // Two clusters of points
@@ -1,5 +1,8 @@
 pub mod formatting;
 pub mod kde;
+pub mod merge;
 pub mod parsing;
+pub mod sampling;
+pub mod sorting;
 pub mod stats;
 pub mod units;
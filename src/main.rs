@@ -1,24 +1,33 @@
-mod formatting;
-mod kde;
-mod parsing;
-mod stats;
-mod units;
-
-use clap::Parser;
-use formatting::{Format, get_display_scale};
-use kde::KDE;
-use rayon::prelude::*;
-use stats::Stats;
+use clap::{Parser, ValueEnum};
+use disty_cli::formatting::{
+    AUTO_SIGNIFICANT_FIGURES, DEFAULT_PRECISION, Format, format_bytes, format_bytes_si,
+    format_duration, format_fixed, format_rate, format_relative, get_display_scale,
+    group_thousands, significant_decimals,
+};
+use disty_cli::kde::{KDE, YAxis};
+use disty_cli::merge;
+use disty_cli::parsing::{self, ParseOptions};
+use disty_cli::sampling::Reservoir;
+use disty_cli::stats::{
+    JackknifeStatistic, QuantileMethod, Stats, Totals, cumulate, mann_whitney, sturges_bins,
+};
+use disty_cli::units::Unit;
+use flate2::read::MultiGzDecoder;
+use regex::bytes::Regex;
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use textplots::{Chart, LabelBuilder, LabelFormat, Plot, Shape};
-use units::Unit;
 
 #[derive(Parser)]
 #[command(about = "Summarizes numerical distributions", version)]
 struct Args {
-    /// Input file (stdin if not specified)
-    input: Option<std::path::PathBuf>,
+    /// Input files, optionally gzip-compressed (stdin if not specified)
+    inputs: Vec<PathBuf>,
 
     /// Input unit
     #[arg(short, long)]
@@ -28,170 +37,3063 @@ struct Args {
     #[arg(short, long)]
     fmt: Option<Format>,
 
-    /// Skip KDE plotting
-    #[arg(long)]
+    /// Skip KDE plotting and print only the stats table
+    #[arg(long, short = 'q', visible_alias = "quiet")]
     no_plot: bool,
-}
 
-fn main() {
-    let args = Args::parse();
+    /// Skip the stats table and print only the plot (with --no-plot, prints nothing)
+    #[arg(long)]
+    no_summary: bool,
 
-    let data = match &args.input {
-        Some(path) => {
-            let file = File::open(path).unwrap_or_else(|e| {
-                eprintln!("error opening {}: {}", path.display(), e);
-                std::process::exit(1);
-            });
-            parsing::read_file_mmap(&file, args.unit)
-        }
-        None => {
-            let reader = Box::new(io::stdin().lock());
-            read_input(reader, args.unit)
-        }
-    };
+    /// Prefix marking comment lines to ignore (empty to disable)
+    #[arg(long, default_value = "#")]
+    comment_char: String,
 
-    if data.is_empty() {
-        eprintln!("no input");
-        return;
-    }
+    /// Parse the leading ASCII bytes of each line, ignoring invalid UTF-8
+    #[arg(long)]
+    tolerant_utf8: bool,
 
-    let format = args
-        .fmt
-        .or_else(|| args.unit.map(|u| u.default_format()))
-        .unwrap_or(Format::Float);
+    /// Parse only this delimited column (1-based); shorter lines are skipped
+    #[arg(long)]
+    column: Option<NonZeroUsize>,
 
-    let stats = Stats::new(data);
+    /// Column delimiter, a single character or \t for tab
+    #[arg(long, default_value = ",", value_parser = parse_delimiter)]
+    delimiter: u8,
 
-    // TODO if no_plot, we should probably just print lines instead of table.
-    print_stats_table(&stats, format);
-    if !args.no_plot {
-        println!();
-        plot_kde(&stats, format);
+    /// Discard the first N non-empty, non-comment lines of each input (e.g. CSV headers)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip_header: usize,
+
+    /// Multiply every parsed value by this factor (combined with --unit's scale if both are given)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_scale)]
+    scale: f64,
+
+    /// Read `,` as the decimal separator and `.` as a thousands separator (e.g. 1.000,5).
+    /// --column splits on --delimiter first, so pair the two with another delimiter like ';'
+    #[arg(long)]
+    decimal_comma: bool,
+
+    /// Accept values with a unit suffix (e.g. 12ms, 4.5MiB); suffixed values ignore --unit but
+    /// still get --scale. 0x/0o/0b integers never take a suffix, so 0x1B is 27
+    #[arg(long)]
+    parse_suffix: bool,
+
+    /// Read the number at KEY from each line of JSON; nested keys are dotted (e.g. timing.total)
+    #[arg(long, value_name = "KEY", conflicts_with = "column")]
+    json_field: Option<String>,
+
+    /// Read inputs as raw little-endian f64 arrays instead of text
+    #[arg(long, conflicts_with_all = ["uniq_c", "parse_suffix", "column", "json_field"])]
+    binary: bool,
+
+    /// Only parse lines matching this regex, e.g. to pick metric lines out of a log. Opt-in,
+    /// since running a regex on every line can take longer than parsing it
+    #[arg(long, value_name = "PATTERN", value_parser = parse_regex, conflicts_with = "binary")]
+    grep: Option<Regex>,
+
+    /// Drop the lines matching --grep instead, like `grep -v`
+    #[arg(long, requires = "grep")]
+    grep_invert: bool,
+
+    /// Split values on any whitespace, not just line breaks, so one line can hold many values
+    /// (e.g. a single-line dump of space-separated hex). Comments must still be whole lines
+    #[arg(long, conflicts_with_all = ["uniq_c", "column", "json_field", "binary"])]
+    token_split: bool,
+
+    /// Parse `count value` lines (e.g. from `sort | uniq -c`) as value repeated count times
+    #[arg(long)]
+    uniq_c: bool,
+
+    /// Show the mean with this fraction of values dropped from each end (0 <= FRACTION < 0.5)
+    #[arg(long, value_name = "FRACTION", value_parser = parse_tail_fraction)]
+    trim: Option<f64>,
+
+    /// Show the mean and variance with this fraction of each tail clamped (0 <= FRACTION < 0.5)
+    #[arg(long, value_name = "FRACTION", value_parser = parse_tail_fraction)]
+    winsorize: Option<f64>,
+
+    /// Show a confidence interval for the mean at this level (e.g. 0.95)
+    #[arg(long, value_name = "LEVEL", value_parser = parse_level)]
+    ci: Option<f64>,
+
+    /// Show a bootstrap confidence interval for --bootstrap-stat from N resamples
+    /// (at the --ci level, 95% by default)
+    #[arg(long, value_name = "N")]
+    bootstrap: Option<usize>,
+
+    /// Statistic to bootstrap (e.g. median, mean, p99)
+    #[arg(long, value_name = "NAME", default_value = "median")]
+    bootstrap_stat: Statistic,
+
+    /// Seed for randomized features (--bootstrap, --sample); if omitted, a random seed is
+    /// chosen and printed to stderr so the run can be reproduced
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Summarize a uniformly random sample of N values instead of the whole input, reservoir
+    /// sampled while reading so huge inputs never need to fit in memory
+    #[arg(long, value_name = "N", value_parser = parse_count)]
+    sample: Option<usize>,
+
+    /// Show the jackknife bias and standard error of this statistic (leave-one-out estimates)
+    #[arg(long, value_name = "NAME")]
+    jackknife: Option<JackknifeStatistic>,
+
+    /// Show the differential entropy of the KDE, in nats (depends on the KDE's bandwidth)
+    #[arg(long)]
+    entropy: bool,
+
+    /// Report values outside the Tukey fences (q25 - k·IQR, q75 + k·IQR)
+    #[arg(long)]
+    outliers: bool,
+
+    /// The IQR multiplier k for outlier fences
+    #[arg(long, value_name = "K", default_value_t = TUKEY_K, value_parser = parse_fence_k)]
+    outliers_k: f64,
+
+    /// Print only this statistic as a raw number in base units (e.g. mean, median, p99, std_dev)
+    #[arg(long, value_name = "NAME")]
+    only: Option<Statistic>,
+
+    /// Show only these statistics, in this order, in place of the default table, e.g.
+    /// n,median,p99. Takes the names --only does; rows from flags like --ci still follow
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        conflicts_with = "robust"
+    )]
+    stats: Option<Vec<Statistic>>,
+
+    /// How percentiles falling between two values are resolved: linear interpolates (R's
+    /// type 7); nearest, lower, and higher take an actual value, as NumPy's methods do
+    #[arg(long, value_name = "METHOD", default_value = "linear")]
+    quantile_method: QuantileMethod,
+
+    /// Print the percentile at which VALUE (in the input unit) falls, e.g. for an SLA threshold
+    #[arg(
+        long,
+        value_name = "VALUE",
+        allow_negative_numbers = true,
+        conflicts_with = "only"
+    )]
+    rank: Option<f64>,
+
+    /// Show each summary statistic as a multiple of VALUE (in the input's units, like
+    /// --rank), e.g. an SLA target, so 1.30× means 30% over it. Zero isn't allowed, since
+    /// every ratio would be infinite
+    #[arg(
+        long,
+        value_name = "VALUE",
+        allow_negative_numbers = true,
+        value_parser = parse_reference,
+        conflicts_with_all = ["display_unit", "show_units", "byte_ambiguity"]
+    )]
+    relative_to: Option<f64>,
+
+    /// Compare the inputs against this baseline with a Mann-Whitney U test
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Write the histogram to PATH as `bin_center,count` CSV rows (centers in base units).
+    /// Bins are equal-width and left-closed, [lower, upper), with the last also including max
+    #[arg(long, value_name = "PATH")]
+    histogram_out: Option<PathBuf>,
+
+    /// Number of histogram bins (Sturges' rule, ⌈log₂ n⌉ + 1, if omitted; with --log-bins,
+    /// one per decade)
+    #[arg(long, value_name = "N")]
+    bins: Option<NonZeroUsize>,
+
+    /// Space the --histogram-out bins geometrically instead, as is usual for latencies, and
+    /// write `bin_lower,bin_upper,count` rows. Without --bins each bin is a decade between
+    /// powers of ten. Needs positive values
+    #[arg(long, requires = "histogram_out")]
+    log_bins: bool,
+
+    /// Write the KDE's evaluation points to PATH as `x,<y-axis>` CSV rows (x in base units),
+    /// over the same range and with the same --y-axis scaling as the plot
+    #[arg(long, value_name = "PATH")]
+    kde_out: Option<PathBuf>,
+
+    /// Number of evenly spaced points written by --kde-out
+    #[arg(long, value_name = "N", default_value = "160")]
+    kde_points: NonZeroUsize,
+
+    /// Which chart to draw below the table
+    #[arg(long, default_value = "kde")]
+    plot: PlotKind,
+
+    /// Draw vertical lines on the KDE plot at these percentiles, with a legend giving their
+    /// values, e.g. --markers=p50,p99 (the = is required). Bare --markers marks the median
+    /// and 95th percentile
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "median,p95",
+        value_parser = parse_marker,
+        conflicts_with = "no_plot"
+    )]
+    markers: Option<Vec<Statistic>>,
+
+    /// Drop values outside LO,HI before analysis. Bounds are raw values in the input unit, or
+    /// percentiles with a `p` prefix (e.g. `p1,p99`); either side may be left empty
+    #[arg(long, value_name = "LO,HI", allow_hyphen_values = true)]
+    clip: Option<Clip>,
+
+    /// Zoom the KDE or CDF plot into the values above this quantile (e.g. 0.9); the table
+    /// still covers everything
+    #[arg(long, value_name = "Q", value_parser = parse_tail_quantile)]
+    tail: Option<f64>,
+
+    /// Fit the KDE or CDF plot's x-axis to the values between the P and 1 - P quantiles, so
+    /// a few extreme values don't squash the rest; the curve and the table still use every
+    /// value. Bare --plot-range uses P = 0.005 (the 0.5th to 99.5th percentile)
+    #[arg(
+        long,
+        value_name = "P",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0.005",
+        value_parser = parse_tail_fraction,
+        conflicts_with = "tail"
+    )]
+    plot_range: Option<f64>,
+
+    /// Use an adaptive-bandwidth KDE (Abramson's rule) for the plot, mode, and entropy:
+    /// sharper in dense regions, smoother in sparse tails, at roughly twice the cost
+    #[arg(long)]
+    adaptive: bool,
+
+    /// Pick the KDE bandwidth from min(σ, IQR / 1.34) rather than σ, so a few extreme values
+    /// don't oversmooth the curve (Silverman's robust rule)
+    #[arg(long)]
+    robust_bandwidth: bool,
+
+    /// How to express the KDE plot's y-axis
+    #[arg(long, default_value = "density")]
+    y_axis: YAxis,
+
+    /// Stop reading after the first N values, across the inputs in order. Files are parsed
+    /// from the front in rounds of blocks (one per thread) until N values turn up, so only
+    /// about that much of a huge file is read, but with less parallelism than a full read
+    #[arg(long, value_name = "N", value_parser = parse_count, conflicts_with = "sample")]
+    head: Option<usize>,
+
+    /// Keep only the last N values read (after --head, if both are given). Named --last since
+    /// --tail sets the plot's upper quantile. The whole input is still parsed
+    #[arg(long, value_name = "N", value_parser = parse_count, conflicts_with = "sample")]
+    last: Option<usize>,
+
+    /// Track the line each value came from and show where the min and max occurred. Sorting
+    /// loses the input order, so an unsorted copy of the values is kept with their positions,
+    /// which takes about four times the memory of a plain read
+    #[arg(long, conflicts_with_all = ["sample", "head", "follow"])]
+    with_index: bool,
+
+    /// Skip sorting because the input (all inputs, concatenated) is already in ascending order.
+    /// Unsorted input then gives wrong quantiles; only debug builds check
+    #[arg(long, conflicts_with = "sample")]
+    assume_sorted: bool,
+
+    /// Exit with an error if fewer than N values were read (e.g. an empty or truncated input)
+    #[arg(long, value_name = "N")]
+    min_count: Option<usize>,
+
+    /// Exit with an error if any value is NaN or infinite instead of ignoring it
+    #[arg(long)]
+    fail_on_nan: bool,
+
+    /// Drop values that are exactly zero (e.g. no-op measurements) before summarizing, so
+    /// the geometric and harmonic means and log-scale plots still work
+    #[arg(long)]
+    exclude_zeros: bool,
+
+    /// Compute gmean and gsd over just the positive values instead of showing neither when
+    /// any value is zero or negative. Caveat: those values have no logarithm, so this
+    /// summarizes a different population and overstates the mean of the whole dataset.
+    #[arg(long)]
+    geomean_ignore_nonpositive: bool,
+
+    /// Always show the gmean and gsd rows, as NaN when they're undefined, so tables from
+    /// different datasets line up
+    #[arg(long)]
+    geomean_always: bool,
+
+    /// Show outlier-resistant statistics (trimmed mean, MAD, IQR, Tukey outlier count)
+    /// in place of the mean, std dev, and variance
+    #[arg(long)]
+    robust: bool,
+
+    /// Decimal places shown for values
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_PRECISION)]
+    precision: usize,
+
+    /// Choose each value's decimal places from its magnitude to show 3 significant figures,
+    /// so e.g. 0.0000123 and 12346 are both readable, instead of a fixed --precision
+    #[arg(long, conflicts_with = "precision")]
+    precision_auto: bool,
+
+    /// Insert thousands separators into float values (e.g. 1,001,001.00)
+    #[arg(long)]
+    group_digits: bool,
+
+    /// Highlight key rows of the table (auto: only on a terminal without NO_COLOR set)
+    #[arg(long, default_value = "auto")]
+    color: ColorChoice,
+
+    /// How values line up in the table (auto: decimal points, except in hex)
+    #[arg(long, default_value = "auto")]
+    align: Align,
+
+    /// How to render the summary; markdown and list skip the plot
+    #[arg(long, default_value = "table")]
+    output_format: OutputFormat,
+
+    /// Join each label and value with STR (e.g. =, :, or \t for tab) instead of aligning
+    /// them, in --output-format list
+    #[arg(long, value_name = "STR", value_parser = parse_field_sep)]
+    field_sep: Option<String>,
+
+    /// Show every value in this unit instead of auto-scaling (must match the output format)
+    #[arg(long)]
+    display_unit: Option<Unit>,
+
+    /// Show every value in one consistent unit, labeling raw floats with their base unit
+    #[arg(long)]
+    show_units: bool,
+
+    /// Show byte values with both decimal (KB) and binary (KiB) prefixes
+    #[arg(long)]
+    byte_ambiguity: bool,
+
+    /// Allow a --fmt that measures something other than --unit, like --unit ms --fmt bytes,
+    /// and skip the warning when values don't look like the --unit's dimension
+    #[arg(long)]
+    force: bool,
+
+    /// Print values instead of summarizing them
+    #[arg(long)]
+    dump: Option<Dump>,
+
+    /// Don't show a progress line on stderr while reading large files
+    #[arg(long)]
+    no_progress: bool,
+
+    /// Print how long parsing and computing the statistics took to stderr, with the
+    /// parsing rate in values (and, for whole files, bytes) per second
+    #[arg(long, conflicts_with = "follow")]
+    timing: bool,
+
+    /// Also read values piped to stdin and pool them with the input files' values. Explicit
+    /// rather than automatic, since a script's stdin often isn't a terminal by accident
+    #[arg(long, requires = "inputs", conflicts_with_all = ["sample", "head", "with_index"])]
+    merge_stdin: bool,
+
+    /// Only count and sum the values (showing n, sum, and mean), accumulating them while
+    /// parsing without keeping or sorting the values. Much faster and lighter for huge inputs
+    #[arg(
+        long,
+        conflicts_with_all = ["sample", "head", "last", "with_index", "clip", "exclude_zeros", "dump"]
+    )]
+    count_only: bool,
+
+    /// Summarize the running total of the values, in input order (inputs one after another),
+    /// instead of the values themselves: for inputs that are increments. --head and --last
+    /// pick the increments to total
+    #[arg(long, conflicts_with_all = ["sample", "count_only"])]
+    cumulative: bool,
+
+    /// Keep watching the input files (plain text only) and redraw the summary as lines are
+    /// appended, every --interval seconds. Runs until interrupted with Ctrl-C
+    #[arg(
+        long,
+        requires = "inputs",
+        conflicts_with_all = ["binary", "dump", "sample", "head", "last", "merge_stdin", "count_only", "cumulative"]
+    )]
+    follow: bool,
+
+    /// Seconds between refreshes in --follow mode
+    #[arg(long, value_name = "SECS", default_value = "1", value_parser = parse_interval)]
+    interval: Duration,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Space-aligned columns for the terminal
+    Table,
+    /// GitHub-flavored Markdown table, for pasting into issues and PRs
+    Markdown,
+    /// One row per line, the percentiles after the other statistics, for scripts
+    List,
+}
+
+/// A single statistic that can be requested by name
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Statistic {
+    N,
+    Sum,
+    Mean,
+    GeoMean,
+    HarmonicMean,
+    Mode,
+    StdDev,
+    Sem,
+    Variance,
+    Cv,
+    Mad,
+    Iqr,
+    /// Any quantile: min, median, max, or pNN such as p99 or p99.9
+    Quantile(f64),
+}
+
+impl std::str::FromStr for Statistic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "n" => Statistic::N,
+            "sum" => Statistic::Sum,
+            "mean" => Statistic::Mean,
+            "gmean" => Statistic::GeoMean,
+            "hmean" => Statistic::HarmonicMean,
+            "mode" => Statistic::Mode,
+            "std_dev" | "stddev" => Statistic::StdDev,
+            "sem" => Statistic::Sem,
+            "variance" => Statistic::Variance,
+            "cv" => Statistic::Cv,
+            "mad" => Statistic::Mad,
+            "iqr" => Statistic::Iqr,
+            "min" => Statistic::Quantile(0.0),
+            "median" => Statistic::Quantile(0.5),
+            "max" => Statistic::Quantile(1.0),
+            _ => match s.strip_prefix('p').map(str::parse::<f64>) {
+                Some(Ok(p)) if (0.0..=100.0).contains(&p) => Statistic::Quantile(p / 100.0),
+                _ => {
+                    return Err(format!(
+                        "unknown statistic '{}' (expected n, sum, mean, gmean, hmean, mode, \
+                         std_dev, sem, variance, cv, mad, iqr, min, median, max, or pNN)",
+                        s
+                    ));
+                }
+            },
+        })
     }
 }
 
-/// Parses numeric input (decimal or hex with 0x prefix) from buffered reader.
-/// All values are scaled to base units (nanoseconds for time, bytes for size).
-fn read_input(reader: Box<dyn BufRead>, unit: Option<Unit>) -> Vec<f64> {
-    let scale = unit.map(|u| u.scale()).unwrap_or(1.0);
-    let mut values = Vec::new();
+impl Statistic {
+    /// The statistic's value. The mode comes from the same KDE as the table and plot, so it
+    /// follows --adaptive and --robust-bandwidth.
+    fn value(&self, stats: &Stats, args: &Args) -> f64 {
+        match self {
+            Statistic::N => stats.n as f64,
+            Statistic::Sum => stats.sum,
+            Statistic::Mean => stats.mean,
+            Statistic::GeoMean => stats.geo_mean,
+            Statistic::HarmonicMean => stats.harmonic_mean,
+            Statistic::Mode => build_kde(stats, args).mode(MODE_RESOLUTION),
+            Statistic::StdDev => stats.std_dev,
+            Statistic::Sem => stats.sem(),
+            Statistic::Variance => stats.variance,
+            Statistic::Cv => stats.cv(),
+            Statistic::Mad => stats.mad(),
+            Statistic::Iqr => stats.iqr(),
+            Statistic::Quantile(q) => stats.quantile(*q),
+        }
+    }
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(e) => {
-                eprintln!("error reading input: {}", e);
-                std::process::exit(1);
+    /// Row label in the stats table
+    fn label(&self) -> Cow<'static, str> {
+        let label = match self {
+            Statistic::N => "n",
+            Statistic::Sum => "sum",
+            Statistic::Mean => "mean",
+            Statistic::GeoMean => "gmean",
+            Statistic::HarmonicMean => "hmean",
+            Statistic::Mode => "mode",
+            Statistic::StdDev => "std dev",
+            Statistic::Sem => "sem",
+            Statistic::Variance => "variance",
+            Statistic::Cv => "cv",
+            Statistic::Mad => "MAD",
+            Statistic::Iqr => "IQR",
+            Statistic::Quantile(q) if *q == 0.0 => "min",
+            Statistic::Quantile(q) if *q == 0.5 => "median",
+            Statistic::Quantile(q) if *q == 1.0 => "max",
+            Statistic::Quantile(q) => {
+                // Rounded so p99.9 reads back as 99.9 rather than 99.90000000000001
+                let percent = (q * 100.0 * 1e10).round() / 1e10;
+                return Cow::Owned(format!("{}%ile", percent));
             }
         };
+        Cow::Borrowed(label)
+    }
+}
 
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ClipBound {
+    /// A raw value in the input unit
+    Value(f64),
+    /// A percentile of the data, 0 to 100
+    Percentile(f64),
+}
+
+impl ClipBound {
+    /// Resolves to a value in base units
+    fn resolve(&self, stats: &Stats, scale: f64) -> f64 {
+        match self {
+            ClipBound::Value(v) => v * scale,
+            ClipBound::Percentile(p) => stats.quantile(p / 100.0),
         }
+    }
+}
 
-        let value = if let Some(hex) = trimmed.strip_prefix("0x") {
-            match u64::from_str_radix(hex, 16) {
-                Ok(v) => v as f64,
-                Err(e) => {
-                    eprintln!("error parsing hex '{}': {}", trimmed, e);
-                    std::process::exit(1);
-                }
+/// Inclusive range of values to keep; a missing bound is unbounded
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Clip {
+    low: Option<ClipBound>,
+    high: Option<ClipBound>,
+}
+
+impl std::str::FromStr for Clip {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (low, high) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected LO,HI, got '{}'", s))?;
+        let bound = |text: &str| -> Result<Option<ClipBound>, String> {
+            let text = text.trim();
+            if text.is_empty() {
+                return Ok(None);
             }
-        } else {
-            match trimmed.parse::<f64>() {
-                Ok(v) => v,
-                Err(e) => {
-                    eprintln!("error parsing number '{}': {}", trimmed, e);
-                    std::process::exit(1);
-                }
+            match text.strip_prefix('p') {
+                Some(p) => match p.parse::<f64>() {
+                    Ok(p) if (0.0..=100.0).contains(&p) => Ok(Some(ClipBound::Percentile(p))),
+                    _ => Err(format!("invalid percentile '{}'", text)),
+                },
+                None => text
+                    .parse::<f64>()
+                    .map(|v| Some(ClipBound::Value(v)))
+                    .map_err(|_| format!("invalid clip bound '{}'", text)),
             }
         };
+        Ok(Clip {
+            low: bound(low)?,
+            high: bound(high)?,
+        })
+    }
+}
+
+impl Clip {
+    /// Keeps the values within the bounds, returning the clipped stats and how many were dropped
+    fn apply(&self, stats: Stats, scale: f64) -> (Stats, usize) {
+        let low = self
+            .low
+            .map_or(f64::NEG_INFINITY, |b| b.resolve(&stats, scale));
+        let high = self
+            .high
+            .map_or(f64::INFINITY, |b| b.resolve(&stats, scale));
 
-        values.push(value * scale);
+        // Data is sorted, so the kept values are one contiguous slice
+        let start = stats.data.partition_point(|&x| x < low);
+        let end = stats.data.partition_point(|&x| x <= high).max(start);
+        let clipped = stats.n - (end - start);
+        let kept = Stats::from_sorted(stats.data[start..end].to_vec())
+            .with_quantile_method(stats.quantile_method)
+            .with_positive_geometric(stats.positive_geometric);
+        (kept, clipped)
     }
+}
 
-    values
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum PlotKind {
+    /// Kernel density estimate
+    Kde,
+    /// Empirical cumulative distribution (read percentiles off the y-axis)
+    Cdf,
+    /// One-line box plot: IQR box, median, whiskers to the Tukey fences, `o` for outliers
+    Box,
 }
 
-fn print_stats_table(stats: &Stats, format: Format) {
-    let mut left_items = vec![
-        ("n", stats.n.to_string()),
-        ("sum", format.format(stats.sum)),
-        ("mean", format.format(stats.mean)),
-    ];
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Align {
+    Auto,
+    /// Line up decimal points (or the ends of whole numbers) within each column
+    Decimal,
+    /// Left-justify values
+    Left,
+}
 
-    if !stats.geo_mean.is_nan() {
-        left_items.push(("gmean", format.format(stats.geo_mean)));
+impl Align {
+    fn decimal(self, format: Format) -> bool {
+        match self {
+            Align::Auto => format != Format::Hex,
+            Align::Decimal => true,
+            Align::Left => false,
+        }
     }
+}
 
-    left_items.push(("std dev", format.format(stats.std_dev)));
-    left_items.push(("variance", format.format(stats.variance)));
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
 
-    let percentiles = [
-        (0.0, "min"),
-        (0.01, "1%ile"),
-        (0.05, "5%ile"),
-        (0.25, "25%ile"),
-        (0.50, "median"),
-        (0.75, "75%ile"),
-        (0.95, "95%ile"),
-        (0.99, "99%ile"),
-        (1.0, "max"),
-    ];
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Dump {
+    /// Stream the sorted union of already-sorted inputs, in base units (like `sort -m`)
+    Merged,
+    /// Every value that would be summarized, sorted, in base units: after --unit, --scale,
+    /// --column, and --clip are applied and non-finite values dropped
+    Sorted,
+}
 
-    let right_items: Vec<(&str, String)> = percentiles
-        .iter()
-        .map(|(q, label)| (*label, format.format(stats.quantile(*q))))
-        .collect();
+/// Picks the seed for every randomized feature, filling in `args.seed` so all consumers agree.
+/// An omitted seed is drawn at random and reported, but only when something will use it.
+fn resolve_seed(args: &mut Args) -> u64 {
+    if let Some(seed) = args.seed {
+        return seed;
+    }
 
-    let max_rows = left_items.len().max(right_items.len());
+    let seed = fastrand::u64(..);
+    if args.bootstrap.is_some() || args.sample.is_some() {
+        eprintln!("seed: {}", seed);
+    }
+    args.seed = Some(seed);
+    seed
+}
 
-    for i in 0..max_rows {
-        if let Some((label, value)) = left_items.get(i) {
-            print!("{:>8}  {:<20}", label, value);
-        } else {
-            print!("{:30}", "");
+fn main() {
+    let mut args = Args::parse();
+    let opts = parse_options(&args);
+    // Kept in base units from here on, like the values it's compared to
+    args.relative_to = args.relative_to.map(|reference| reference * opts.scale);
+    let mut rng = fastrand::Rng::with_seed(resolve_seed(&mut args));
+
+    if args.merge_stdin && io::stdin().is_terminal() {
+        eprintln!("error: --merge-stdin needs input piped to stdin");
+        std::process::exit(1);
+    }
+
+    if let Some(Dump::Merged) = args.dump {
+        if opts.binary {
+            eprintln!("error: --dump merged reads text inputs and can't be used with --binary");
+            std::process::exit(1);
         }
+        dump_merged(&args.inputs, &opts);
+        return;
+    }
 
-        if let Some((label, value)) = right_items.get(i) {
-            println!("{:>8}  {}", label, value);
+    let format = args
+        .fmt
+        .or_else(|| args.unit.map(|u| u.preferred_format()))
+        .or_else(|| args.display_unit.map(|u| u.preferred_format()))
+        .unwrap_or(Format::Float);
+
+    if args.field_sep.is_some() && args.output_format != OutputFormat::List {
+        eprintln!("error: --field-sep only applies to --output-format list");
+        std::process::exit(1);
+    }
+
+    if let (Some(unit), Some(fmt)) = (args.unit, args.fmt)
+        && dimension_mismatch(unit, fmt)
+        && !args.force
+    {
+        eprintln!(
+            "error: --unit {} and --fmt {} measure different things (pass --force to combine them)",
+            unit.label(),
+            fmt.to_possible_value().unwrap().get_name()
+        );
+        std::process::exit(1);
+    }
+
+    if let Some(unit) = args.display_unit
+        && unit.default_format() != format.family()
+    {
+        eprintln!(
+            "error: --display-unit {} doesn't match the output format",
+            unit.label()
+        );
+        std::process::exit(1);
+    }
+
+    if args.follow {
+        follow(&args, &opts, format);
+    }
+
+    if args.count_only {
+        let totals = total_inputs(&args, &opts);
+        warn_integer_limits(&opts);
+        check_min_count(totals.count + totals.non_finite, &args);
+        check_non_finite(totals.non_finite, &args);
+        if totals.count == 0 {
+            eprintln!("no input");
+            return;
+        }
+        print_totals(&totals, format, &args);
+        return;
+    }
+
+    let started = Instant::now();
+    // How many values were parsed, which is more than were kept when sampling
+    let mut indexed = None;
+    let (data, parsed) = if let Some(capacity) = args.sample {
+        let reservoir = sample_inputs(&args, &opts, capacity, &mut rng);
+        let seen = reservoir.seen();
+        if seen > capacity {
+            eprintln!("sampled {} of {} values", capacity, seen);
+        }
+        (reservoir.into_values(), seen)
+    } else if args.with_index {
+        let read = read_indexed(&args, &opts);
+        let data = read.values.clone();
+        let parsed = data.len();
+        indexed = Some(read);
+        (data, parsed)
+    } else if let Some(limit) = args.head {
+        let data = read_head(&args, &opts, limit);
+        let parsed = data.len();
+        (data, parsed)
+    } else {
+        let mut data: Vec<f64> = if args.inputs.is_empty() {
+            read_stdin(&opts)
         } else {
-            println!();
+            args.inputs
+                .iter()
+                .flat_map(|path| read_path(path, &opts, show_progress(&args)))
+                .collect()
+        };
+        if args.merge_stdin {
+            data.extend(read_stdin(&opts));
+        }
+        let parsed = data.len();
+        (data, parsed)
+    };
+
+    warn_integer_limits(&opts);
+    if args.timing {
+        // --head stops early, so the files' sizes aren't what was read
+        let bytes = input_bytes(&args).filter(|_| args.head.is_none());
+        eprintln!("{}", parse_timing(parsed, bytes, started.elapsed()));
+    }
+
+    let mut data = data;
+    if let Some(last) = args.last {
+        data.drain(..data.len().saturating_sub(last));
+        if let Some(indexed) = &mut indexed {
+            indexed.keep_last(last);
+        }
+    }
+    // Before `report` sorts them, while they're still in input order
+    if args.cumulative {
+        cumulate(&mut data);
+        if let Some(indexed) = &mut indexed {
+            indexed.values.clone_from(&data);
         }
     }
+
+    check_min_count(parsed, &args);
+
+    if data.is_empty() {
+        eprintln!("no input");
+        return;
+    }
+
+    report(data, format, &args, &opts, indexed.as_ref());
 }
 
-fn plot_kde(stats: &Stats, format: Format) {
-    let kde = KDE::new(&stats.data);
-    let (min_x, max_x) = kde.bounds();
+fn check_min_count(parsed: usize, args: &Args) {
+    if let Some(min_count) = args.min_count
+        && parsed < min_count
+    {
+        eprintln!(
+            "error: read {} values, but --min-count requires at least {}",
+            parsed, min_count
+        );
+        std::process::exit(1);
+    }
+}
 
-    let (scale, unit_label) = get_display_scale(max_x, format);
+/// Whether --fmt shows a different dimension than --unit measures, e.g. milliseconds as bytes
+fn dimension_mismatch(unit: Unit, format: Format) -> bool {
+    let family = format.family();
+    matches!(family, Format::Time | Format::Bytes | Format::Bits) && family != unit.default_format()
+}
 
-    // Pre-sample KDE in parallel at chart width points
-    // This mimics what textplots does internally for Shape::Continuous,
-    // but parallelizes the expensive kde.pdf() evaluations
-    const CHART_WIDTH: usize = 160;
-    let points: Vec<(f32, f32)> = (0..CHART_WIDTH)
-        .into_par_iter()
-        .map(|i| {
-            // Map pixel coordinate to data coordinate (inv_linear)
-            let x = min_x + (max_x - min_x) * (i as f64 / (CHART_WIDTH - 1) as f64);
-            let y = kde.pdf(x);
-            ((x / scale) as f32, y as f32)
+/// A warning when values don't look like the --unit's dimension: byte and bit counts are
+/// whole numbers, so input where none is probably holds durations or ratios instead
+fn unit_warning(stats: &Stats, unit: Unit) -> Option<String> {
+    let whole = match unit.default_format() {
+        Format::Bytes => "bytes",
+        Format::Bits => "bits",
+        _ => return None,
+    };
+    if stats.data.iter().any(|x| x.fract() == 0.0) {
+        return None;
+    }
+    Some(format!(
+        "warning: no value is a whole number of {}, so --unit {} may be wrong (--force hides this)",
+        whole,
+        unit.label()
+    ))
+}
+
+/// The --timing line for parsing, e.g. "parsed 1,000,000 values in 42.00ms (23.8M/s)",
+/// with a byte rate too when the input size is known
+fn parse_timing(values: usize, bytes: Option<u64>, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let mut rates = format_rate(values as f64 / seconds, 1);
+    if let Some(bytes) = bytes {
+        rates += &format!(", {}/s", format_bytes_si(bytes as f64 / seconds, 1));
+    }
+    format!(
+        "parsed {} values in {} ({})",
+        group_thousands(&values.to_string()),
+        format_duration(elapsed.as_nanos() as f64, 2),
+        rates
+    )
+}
+
+/// Total size of the input files, if they're all regular files (stdin's size is unknown).
+/// Compressed files count their compressed size, as read from disk.
+fn input_bytes(args: &Args) -> Option<u64> {
+    if args.inputs.is_empty() || args.merge_stdin {
+        return None;
+    }
+    args.inputs
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            metadata.is_file().then_some(metadata.len())
         })
-        .collect();
+        .sum()
+}
 
-    let label_formatter = if !unit_label.is_empty() {
-        let unit = unit_label.to_string();
-        LabelFormat::Custom(Box::new(move |v: f32| format!("{:.1}{}", v, unit)))
+/// Warns once each if any 0x/0o/0b value was too large for 64 bits, and so skipped, or too
+/// large to be represented exactly
+fn warn_integer_limits(opts: &ParseOptions) {
+    let overflowed = opts.take_overflowed_integers();
+    if overflowed > 0 {
+        eprintln!(
+            "warning: skipped {} integer inputs too large for 64 bits",
+            overflowed
+        );
+    }
+    let inexact = opts.take_inexact_integers();
+    if inexact > 0 {
+        eprintln!(
+            "warning: {} integer inputs exceed 2^53 and were rounded to the nearest \
+             representable value, so nearby values (e.g. addresses) may be merged",
+            inexact
+        );
+    }
+}
+
+/// Warns about the ignored non-finite values, or with --fail-on-nan exits
+fn check_non_finite(non_finite: usize, args: &Args) {
+    if non_finite == 0 {
+        return;
+    }
+    if args.fail_on_nan {
+        eprintln!(
+            "error: input contains {} non-finite values (NaN or infinity)",
+            non_finite
+        );
+        std::process::exit(1);
+    }
+    eprintln!(
+        "warning: ignored {} non-finite values (NaN or infinity)",
+        non_finite
+    );
+}
+
+/// The --count-only summary
+fn print_totals(totals: &Totals, format: Format, args: &Args) {
+    let fmt = |value: f64| display_value(value, format, None, args);
+    let items: [Row; 3] = [
+        ("n".into(), totals.count.to_string()),
+        ("sum".into(), fmt(totals.sum())),
+        ("mean".into(), fmt(totals.mean())),
+    ];
+    match args.output_format {
+        OutputFormat::Markdown => print!("{}", render_markdown(&items, &[])),
+        OutputFormat::List => print_list(&items, format, args),
+        OutputFormat::Table => print!(
+            "{}",
+            render_table(&aligned(&items, format, args), &[], use_color(args.color))
+        ),
+    }
+}
+
+/// Computes the statistics and prints everything the flags ask for
+fn report(
+    mut data: Vec<f64>,
+    format: Format,
+    args: &Args,
+    opts: &ParseOptions,
+    indexed: Option<&Indexed>,
+) {
+    if args.exclude_zeros {
+        let excluded = exclude_zeros(&mut data);
+        if excluded > 0 {
+            eprintln!("excluded {} zero values", excluded);
+        }
+    }
+
+    let started = Instant::now();
+    let stats = if args.assume_sorted {
+        Stats::from_sorted(data)
     } else {
-        LabelFormat::Value
+        Stats::new(data)
+    }
+    .with_quantile_method(args.quantile_method)
+    .with_positive_geometric(args.geomean_ignore_nonpositive);
+    if args.timing {
+        let elapsed = format_duration(started.elapsed().as_nanos() as f64, 2);
+        eprintln!("computed statistics in {}", elapsed);
+    }
+    check_non_finite(stats.non_finite, args);
+    let stats = match &args.clip {
+        Some(clip) => {
+            let (clipped_stats, clipped) = clip.apply(stats, opts.scale);
+            if clipped > 0 {
+                eprintln!("clipped {} values outside --clip bounds", clipped);
+            }
+            clipped_stats
+        }
+        None => stats,
     };
+    // After clipping, so the count matches the values gmean was computed over
+    if args.geomean_ignore_nonpositive && stats.nonpositive() > 0 {
+        eprintln!(
+            "excluded {} non-positive values from gmean and gsd",
+            stats.nonpositive()
+        );
+    }
+    if let Some(Dump::Sorted) = args.dump {
+        dump_values(&stats.data);
+        return;
+    }
 
-    Chart::new(160, 40, (min_x / scale) as f32, (max_x / scale) as f32)
-        .lineplot(&Shape::Lines(&points))
-        .x_label_format(label_formatter)
-        .y_label_format(LabelFormat::None)
-        .nice();
+    if stats.is_empty() {
+        eprintln!("no finite input");
+        return;
+    }
+
+    if let Some(warning) = args
+        .unit
+        .filter(|_| !args.force)
+        .and_then(|u| unit_warning(&stats, u))
+    {
+        eprintln!("{}", warning);
+    }
+
+    if let Some(path) = &args.histogram_out {
+        let written = if args.log_bins {
+            let Some(edges) = stats.log_bin_edges(args.bins.map(NonZeroUsize::get)) else {
+                eprintln!(
+                    "error: --log-bins needs positive values, but the minimum is {}",
+                    format.format(stats.quantile(0.0))
+                );
+                std::process::exit(1);
+            };
+            write_log_histogram(path, &stats.bin_counts(&edges))
+        } else {
+            let bins = args
+                .bins
+                .map_or_else(|| sturges_bins(stats.n), NonZeroUsize::get);
+            write_histogram(path, &stats.histogram(bins))
+        };
+        if let Err(e) = written {
+            eprintln!("error writing {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(path) = &args.kde_out {
+        if stats.is_constant() {
+            eprintln!("error: --kde-out needs values that aren't all identical");
+            std::process::exit(1);
+        }
+        let points = kde_points(&stats, args, args.kde_points.get());
+        if let Err(e) = write_kde(path, args.y_axis, &points) {
+            eprintln!("error writing {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(value) = args.rank {
+        println!("{}", 100.0 * stats.rank(value * opts.scale));
+        return;
+    }
+
+    if let Some(statistic) = args.only {
+        println!("{}", statistic.value(&stats, args));
+        return;
+    }
+
+    // Dispersion rows and the KDE are meaningless without any spread
+    if let Some(message) = constant_message(&stats, format) {
+        println!("{}", message);
+        return;
+    }
+
+    let (left_items, mut right_items) = summary_columns(&stats, format, args);
+    if let Some(indexed) = indexed {
+        right_items.extend(position_rows(indexed, &stats, args, opts));
+    }
+
+    match args.output_format {
+        OutputFormat::Markdown => {
+            print!("{}", render_markdown(&left_items, &right_items));
+            return;
+        }
+        OutputFormat::List => {
+            print_list(&[left_items, right_items].concat(), format, args);
+            return;
+        }
+        OutputFormat::Table => {}
+    }
+
+    if !args.no_summary {
+        print!(
+            "{}",
+            render_table(
+                &aligned(&left_items, format, args),
+                &aligned(&right_items, format, args),
+                use_color(args.color)
+            )
+        );
+    }
+
+    // Blank lines go between sections, not before the first one
+    let mut printed = !args.no_summary;
+    let mut section = || {
+        if printed {
+            println!();
+        }
+        printed = true;
+    };
+    if args.outliers {
+        section();
+        print!("{}", outlier_report(&stats, format, args));
+    }
+    if let Some(path) = &args.baseline {
+        let baseline = baseline_stats(path, args, opts);
+        section();
+        println!("{}", comparison_verdict(&stats, &baseline));
+    }
+    if !args.no_plot {
+        section();
+        match args.plot {
+            PlotKind::Kde => plot_kde(&stats, format, args),
+            PlotKind::Cdf => plot_cdf(&stats, format, args),
+            PlotKind::Box => plot_box(&stats, format, args),
+        }
+    }
+}
+
+/// Drops values that are exactly zero (--exclude-zeros), returning how many there were
+fn exclude_zeros(data: &mut Vec<f64>) -> usize {
+    let before = data.len();
+    data.retain(|&x| x != 0.0);
+    before - data.len()
+}
+
+/// Reads the --baseline sample and filters it like the inputs (--exclude-zeros, --clip), so
+/// the comparison tests like against like
+fn baseline_stats(path: &Path, args: &Args, opts: &ParseOptions) -> Stats {
+    let mut data = read_path(path, opts, show_progress(args));
+    if args.exclude_zeros {
+        let excluded = exclude_zeros(&mut data);
+        if excluded > 0 {
+            eprintln!("excluded {} zero values from the baseline", excluded);
+        }
+    }
+
+    let baseline = Stats::new(data).with_quantile_method(args.quantile_method);
+    match &args.clip {
+        Some(clip) => {
+            let (clipped_stats, clipped) = clip.apply(baseline, opts.scale);
+            if clipped > 0 {
+                eprintln!("clipped {} baseline values outside --clip bounds", clipped);
+            }
+            clipped_stats
+        }
+        None => baseline,
+    }
+}
+
+fn write_histogram(path: &Path, histogram: &[(f64, usize)]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "bin_center,count")?;
+    for (center, count) in histogram {
+        writeln!(out, "{},{}", center, count)?;
+    }
+    out.flush()
+}
+
+fn write_log_histogram(path: &Path, bins: &[(f64, f64, usize)]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "bin_lower,bin_upper,count")?;
+    for (lower, upper, count) in bins {
+        writeln!(out, "{},{},{}", lower, upper, count)?;
+    }
+    out.flush()
+}
+
+fn write_kde(path: &Path, y_axis: YAxis, points: &[(f64, f64)]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let label = y_axis.to_possible_value().unwrap();
+    writeln!(out, "x,{}", label.get_name())?;
+    for (x, y) in points {
+        writeln!(out, "{},{}", x, y)?;
+    }
+    out.flush()
+}
+
+fn parse_options(args: &Args) -> ParseOptions {
+    if args.decimal_comma && args.column.is_some() && args.delimiter == b',' {
+        eprintln!("error: --decimal-comma with --column needs a --delimiter other than ','");
+        std::process::exit(1);
+    }
+
+    let mut opts = ParseOptions {
+        comment: args.comment_char.clone().into_bytes(),
+        tolerant_utf8: args.tolerant_utf8,
+        field: args.column.map(|c| c.get() - 1),
+        delimiter: args.delimiter,
+        header_lines: args.skip_header,
+        uniq_c: args.uniq_c,
+        parse_suffix: args.parse_suffix,
+        binary: args.binary,
+        json_path: args
+            .json_field
+            .as_ref()
+            .map(|key| key.split('.').map(String::from).collect()),
+        decimal_comma: args.decimal_comma,
+        token_split: args.token_split,
+        filter: args.grep.clone(),
+        invert_filter: args.grep_invert,
+        ..ParseOptions::new(args.unit)
+    };
+    opts.scale *= args.scale;
+    opts.custom_scale = args.scale;
+    opts
+}
+
+fn open_input(path: &Path) -> File {
+    File::open(path).unwrap_or_else(|e| {
+        eprintln!("error opening {}: {}", path.display(), e);
+        std::process::exit(1);
+    })
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Checks for the gzip magic bytes, leaving the file positioned at the start
+fn is_gzip(file: &mut File) -> bool {
+    let mut magic = [0u8; 2];
+    let detected = file.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC;
+    file.seek(SeekFrom::Start(0)).unwrap_or_else(|e| {
+        eprintln!("error reading input: {}", e);
+        std::process::exit(1);
+    });
+    detected
+}
+
+fn read_stdin(opts: &ParseOptions) -> Vec<f64> {
+    if opts.binary {
+        parsing::read_binary(io::stdin().lock(), opts)
+    } else {
+        parsing::read_buffered(io::stdin().lock(), opts)
+    }
+}
+
+/// An opened input path: a regular file to mmap and parse in parallel, or a stream to read
+/// sequentially
+enum Input {
+    Mapped(File),
+    /// Gzip-compressed (a compressed stream can't be split into independently parseable
+    /// chunks), or not a regular file, like the pipe behind `<(cmd)`, which can't be mapped.
+    /// Read with `ParseOptions::skipping_invalid`, so they parse like mapped files.
+    Stream(Box<dyn BufRead>),
+}
+
+fn open(path: &Path) -> Input {
+    let mut file = open_input(path);
+    if file.metadata().is_ok_and(|m| m.is_file()) {
+        return if is_gzip(&mut file) {
+            Input::Stream(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+        } else {
+            Input::Mapped(file)
+        };
+    }
+
+    // A pipe can't seek back over the magic bytes, so peek at them in the buffer instead
+    let mut reader = BufReader::new(file);
+    let gzip = reader
+        .fill_buf()
+        .is_ok_and(|buf| buf.starts_with(&GZIP_MAGIC));
+    if gzip {
+        Input::Stream(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Input::Stream(Box::new(reader))
+    }
+}
+
+/// Reads a file via mmap, or sequentially when it's a stream (see `Input`)
+fn read_path(path: &Path, opts: &ParseOptions, progress: bool) -> Vec<f64> {
+    match open(path) {
+        Input::Mapped(file) => {
+            let size = file.metadata().map_or(0, |m| m.len()) as usize;
+            if progress && size >= PROGRESS_MIN_BYTES {
+                read_with_progress(path, &file, size, opts)
+            } else {
+                parsing::read_file_mmap(&file, opts)
+            }
+        }
+        Input::Stream(reader) if opts.binary => parsing::read_binary(reader, opts),
+        Input::Stream(reader) => parsing::read_buffered(reader, &opts.skipping_invalid()),
+    }
+}
+
+/// Progress goes to stderr, so it's only drawn there for a person watching a terminal
+fn show_progress(args: &Args) -> bool {
+    !args.no_progress && io::stderr().is_terminal()
+}
+
+/// Files at least this large get a progress line while they're parsed
+const PROGRESS_MIN_BYTES: usize = 64 << 20;
+
+/// Time between progress line redraws
+const PROGRESS_REFRESH: Duration = Duration::from_millis(100);
+
+/// Parses a large file while a second thread redraws a progress line on stderr from the
+/// bytes the parser's workers have reported, erasing it once parsing finishes
+fn read_with_progress(path: &Path, file: &File, size: usize, opts: &ParseOptions) -> Vec<f64> {
+    let progress = AtomicUsize::new(0);
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        let renderer = scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                let read = progress.load(Ordering::Relaxed);
+                eprint!(
+                    "\r\x1b[2Kreading {}: {:>3}% of {}",
+                    path.display(),
+                    100 * read / size.max(1),
+                    format_bytes(size as f64, 1)
+                );
+                std::thread::park_timeout(PROGRESS_REFRESH);
+            }
+            eprint!("\r\x1b[2K");
+        });
+
+        let values = parsing::read_file_mmap_with_progress(file, opts, &progress);
+        done.store(true, Ordering::Relaxed);
+        renderer.thread().unpark();
+        values
+    })
+}
+
+/// Re-reads the inputs' appended lines every `--interval` and redraws the report from the top
+/// of the screen. Never returns; the process runs until it's interrupted.
+fn follow(args: &Args, opts: &ParseOptions, format: Format) -> ! {
+    let mut tails: Vec<(&PathBuf, parsing::Tail)> = args
+        .inputs
+        .iter()
+        .map(|path| (path, parsing::Tail::new(open_input(path), opts)))
+        .collect();
+    let mut data = Vec::new();
+
+    loop {
+        for (path, tail) in &mut tails {
+            match tail.read_appended(opts) {
+                Ok(values) => data.extend(values),
+                Err(e) => {
+                    eprintln!("error reading {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        // Clear the screen and move the cursor home, so each refresh draws in place
+        print!("\x1b[2J\x1b[H");
+        if data.is_empty() {
+            println!("waiting for input...");
+        } else {
+            report(data.clone(), format, args, opts, None);
+        }
+        let _ = io::stdout().flush();
+
+        std::thread::sleep(args.interval);
+    }
+}
+
+/// Reservoir-samples `capacity` values across all inputs, so they're never fully loaded.
+/// Plain files are sampled per mmap chunk in parallel; streams (stdin, gzip) sequentially.
+fn sample_inputs(
+    args: &Args,
+    opts: &ParseOptions,
+    capacity: usize,
+    rng: &mut fastrand::Rng,
+) -> Reservoir {
+    let mut reservoir = Reservoir::new(capacity);
+    if args.inputs.is_empty() {
+        if opts.binary {
+            reservoir.extend(parsing::read_binary(io::stdin().lock(), opts), rng);
+        } else {
+            reservoir.extend(parsing::values(io::stdin().lock(), opts), rng);
+        }
+        return reservoir;
+    }
+
+    for path in &args.inputs {
+        let sample = match open(path) {
+            Input::Mapped(file) => parsing::sample_file_mmap(&file, opts, capacity, rng.u64(..)),
+            Input::Stream(reader) => {
+                let mut sample = Reservoir::new(capacity);
+                if opts.binary {
+                    sample.extend(parsing::read_binary(reader, opts), rng);
+                } else {
+                    sample.extend(parsing::values(reader, &opts.skipping_invalid()), rng);
+                }
+                sample
+            }
+        };
+        reservoir = reservoir.merge(sample, rng);
+    }
+    reservoir
+}
+
+/// Counts and sums every input (and with --merge-stdin, stdin) without keeping the values
+fn total_inputs(args: &Args, opts: &ParseOptions) -> Totals {
+    let mut totals = Totals::default();
+    if args.inputs.is_empty() || args.merge_stdin {
+        totals = totals.merge(total_stream(io::stdin().lock(), opts));
+    }
+
+    for path in &args.inputs {
+        let file_totals = match open(path) {
+            Input::Mapped(file) => parsing::total_file_mmap(&file, opts),
+            Input::Stream(reader) => total_stream(reader, &opts.skipping_invalid()),
+        };
+        totals = totals.merge(file_totals);
+    }
+    totals
+}
+
+fn total_stream(reader: impl BufRead, opts: &ParseOptions) -> Totals {
+    let mut totals = Totals::default();
+    if opts.binary {
+        parsing::read_binary(reader, opts)
+            .into_iter()
+            .for_each(|x| totals.push(x));
+    } else {
+        parsing::values(reader, opts).for_each(|x| totals.push(x));
+    }
+    totals
+}
+
+/// Reads every input like `read_path`, keeping each value's position (--with-index)
+fn read_indexed(args: &Args, opts: &ParseOptions) -> Indexed {
+    let mut indexed = Indexed {
+        values: Vec::new(),
+        positions: Vec::new(),
+    };
+    if args.inputs.is_empty() {
+        indexed.push(0, indexed_stream(io::stdin().lock(), opts));
+        return indexed;
+    }
+
+    for (input, path) in args.inputs.iter().enumerate() {
+        match open(path) {
+            Input::Mapped(file) => {
+                indexed.push(input, parsing::read_file_mmap_indexed(&file, opts))
+            }
+            Input::Stream(reader) => {
+                indexed.push(input, indexed_stream(reader, &opts.skipping_invalid()))
+            }
+        }
+    }
+    indexed
+}
+
+fn indexed_stream(reader: impl BufRead, opts: &ParseOptions) -> Vec<(f64, usize)> {
+    if opts.binary {
+        parsing::read_binary(reader, opts)
+            .into_iter()
+            .zip(1..)
+            .collect()
+    } else {
+        parsing::indexed_values(reader, opts).collect()
+    }
+}
+
+/// Reads the first `limit` values across the inputs in order, opening each input only if
+/// the ones before it came up short. Streams (stdin, gzip, pipes) stop reading once they have
+/// enough.
+fn read_head(args: &Args, opts: &ParseOptions, limit: usize) -> Vec<f64> {
+    if args.inputs.is_empty() {
+        return head_of_stream(io::stdin().lock(), opts, limit);
+    }
+
+    let mut data = Vec::new();
+    for path in &args.inputs {
+        let remaining = limit - data.len();
+        if remaining == 0 {
+            break;
+        }
+        match open(path) {
+            Input::Mapped(file) => {
+                data.extend(parsing::read_file_mmap_head(&file, opts, remaining))
+            }
+            Input::Stream(reader) => {
+                data.extend(head_of_stream(reader, &opts.skipping_invalid(), remaining))
+            }
+        }
+    }
+    data
+}
+
+fn head_of_stream(reader: impl BufRead, opts: &ParseOptions, limit: usize) -> Vec<f64> {
+    if opts.binary {
+        parsing::read_binary(reader.take(limit as u64 * 8), opts)
+    } else {
+        parsing::values(reader, opts).take(limit).collect()
+    }
+}
+
+/// Opens a file for sequential reading, transparently decompressing gzip
+fn open_reader(path: &Path) -> Box<dyn BufRead> {
+    match open(path) {
+        Input::Mapped(file) => Box::new(BufReader::new(file)),
+        Input::Stream(reader) => reader,
+    }
+}
+
+/// Writes the merged values as they're produced, so inputs are never fully loaded into memory
+fn dump_merged(inputs: &[PathBuf], opts: &ParseOptions) {
+    // Files skip invalid lines as they would when mapped; only stdin is strict
+    let file_opts = opts.skipping_invalid();
+    let (readers, opts): (Vec<Box<dyn BufRead>>, _) = if inputs.is_empty() {
+        (vec![Box::new(io::stdin().lock())], opts)
+    } else {
+        (
+            inputs.iter().map(|path| open_reader(path)).collect(),
+            &file_opts,
+        )
+    };
+    let sources = readers
+        .into_iter()
+        .map(|reader| parsing::values(reader, opts))
+        .collect();
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    for value in merge::merge_sorted(sources) {
+        if writeln!(out, "{}", value).is_err() {
+            // Downstream closed (e.g. piped into head)
+            return;
+        }
+    }
+    let _ = out.flush();
+}
+
+fn dump_values(values: &[f64]) {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for value in values {
+        if writeln!(out, "{}", value).is_err() {
+            // Downstream closed (e.g. piped into head)
+            return;
+        }
+    }
+    let _ = out.flush();
+}
+
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s {
+        "\\t" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(format!("expected a single ASCII character, got '{}'", s)),
+    }
+}
+
+fn parse_field_sep(s: &str) -> Result<String, String> {
+    match s {
+        "" => Err("the separator can't be empty".to_string()),
+        "\\t" => Ok("\t".to_string()),
+        _ => Ok(s.to_string()),
+    }
+}
+
+fn parse_scale(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(factor),
+        Ok(_) => Err(format!("scale must be positive and finite, got '{}'", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| e.to_string())
+}
+
+/// A --markers entry, which has to be a percentile to have a place on the x-axis
+fn parse_marker(s: &str) -> Result<Statistic, String> {
+    match s.parse()? {
+        statistic @ Statistic::Quantile(_) => Ok(statistic),
+        _ => Err(format!(
+            "'{}' isn't a percentile (expected min, median, max, or pNN)",
+            s
+        )),
+    }
+}
+
+/// A --relative-to reference, which must be finite and nonzero to divide by
+fn parse_reference(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(reference) if reference.is_finite() && reference != 0.0 => Ok(reference),
+        Ok(_) => Err(format!("reference must be finite and nonzero, got '{}'", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(Duration::from_secs_f64(secs)),
+        Ok(_) => Err(format!("interval must be positive, got '{}'", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A fraction of values to cut or clamp at each end, which must leave something in the middle
+fn parse_tail_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(fraction) if (0.0..0.5).contains(&fraction) => Ok(fraction),
+        Ok(_) => Err(format!("fraction must be in [0, 0.5), got '{}'", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A count of values to keep (--sample, --head, --last), which must be at least 1
+fn parse_count(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_level(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(level) if level > 0.0 && level < 1.0 => Ok(level),
+        Ok(_) => Err(format!(
+            "confidence level must be between 0 and 1, got '{}'",
+            s
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Row label for a confidence level, e.g. "95% CI"
+fn ci_label(level: f64) -> String {
+    format!("{}% CI", 100.0 * level)
+}
+
+fn parse_tail_quantile(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(q) if (0.0..1.0).contains(&q) => Ok(q),
+        Ok(_) => Err(format!("quantile must be in [0, 1), got '{}'", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_fence_k(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(k) if k.is_finite() && k >= 0.0 => Ok(k),
+        Ok(_) => Err(format!("k must be non-negative and finite, got '{}'", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn constant_message(stats: &Stats, format: Format) -> Option<String> {
+    if !stats.is_constant() {
+        return None;
+    }
+
+    let value = format.format(stats.data[0]);
+    Some(if stats.n == 1 {
+        format!("single value: {}", value)
+    } else {
+        format!("all {} values are identical: {}", stats.n, value)
+    })
+}
+
+/// Trim fraction used for the trimmed mean in the robust preset
+const ROBUST_TRIM: f64 = 0.1;
+
+/// Multiplier on the IQR for Tukey's outlier fences
+const TUKEY_K: f64 = 1.5;
+
+/// Number of KDE evaluation points when integrating the entropy
+const ENTROPY_RESOLUTION: usize = 2000;
+
+/// Confidence level of the bootstrap interval when --ci isn't given
+const DEFAULT_BOOTSTRAP_LEVEL: f64 = 0.95;
+
+/// Number of KDE evaluation points when searching for the mode
+const MODE_RESOLUTION: usize = 1000;
+
+type Row = (Cow<'static, str>, String);
+
+/// The (scale, label) every table value is locked to, if any.
+/// `--display-unit` wins; `--show-units` picks one unit for the whole table from its largest
+/// value, and labels raw floats with the base unit (ns or B) of the input unit.
+fn fixed_unit(stats: &Stats, format: Format, args: &Args) -> Option<(f64, &'static str)> {
+    if let Some(unit) = args.display_unit {
+        return Some((unit.scale(), unit.label()));
+    }
+    if !args.show_units {
+        return None;
+    }
+
+    match format {
+        Format::Time | Format::Bytes | Format::BytesSi | Format::Bits | Format::Rate => {
+            let max_abs = stats.quantile(0.0).abs().max(stats.quantile(1.0).abs());
+            Some(get_display_scale(max_abs, format))
+        }
+        Format::Float => args
+            .unit
+            .map(|unit| get_display_scale(0.0, unit.default_format())),
+        Format::Hex | Format::Scientific => None,
+    }
+}
+
+/// Formats a value for the table, applying display options on top of the base format
+fn display_value(value: f64, format: Format, fixed: Option<(f64, &str)>, args: &Args) -> String {
+    let decimals_for = |shown: f64| plain_decimals(shown, args);
+    let precision = |format: Format| {
+        if args.precision_auto {
+            format.auto_precision(value, AUTO_SIGNIFICANT_FIGURES)
+        } else {
+            args.precision
+        }
+    };
+
+    if let Some(reference) = args.relative_to {
+        return format_relative(value, reference, decimals_for(value / reference));
+    }
+    if let Some((scale, label)) = fixed {
+        return format_fixed(value, scale, label, decimals_for(value / scale));
+    }
+
+    match format {
+        Format::Bytes | Format::BytesSi if args.byte_ambiguity => {
+            format!(
+                "{} / {}",
+                format_bytes_si(value, precision(Format::BytesSi)),
+                format_bytes(value, precision(Format::Bytes))
+            )
+        }
+        Format::Float if args.group_digits => {
+            group_thousands(&format.format_with(value, precision(format)))
+        }
+        _ => format.format_with(value, precision(format)),
+    }
+}
+
+/// Decimal places for a number shown as is: --precision, or with --precision-auto, enough
+/// for `AUTO_SIGNIFICANT_FIGURES` of that number
+fn plain_decimals(shown: f64, args: &Args) -> usize {
+    if args.precision_auto {
+        significant_decimals(shown, AUTO_SIGNIFICANT_FIGURES)
+    } else {
+        args.precision
+    }
+}
+
+/// Builds the (left, right) columns of the stats table
+fn summary_columns(stats: &Stats, format: Format, args: &Args) -> (Vec<Row>, Vec<Row>) {
+    let fixed = fixed_unit(stats, format, args);
+    let fmt = |value: f64| display_value(value, format, fixed, args);
+    let mut left_items = match &args.stats {
+        Some(selection) => selected_rows(selection, stats, args, &fmt),
+        None => default_rows(stats, args, &fmt),
+    };
+
+    if args.entropy {
+        // Dimensionless (nats), so it ignores the selected format
+        let entropy = build_kde(stats, args).entropy(ENTROPY_RESOLUTION);
+        left_items.push((
+            "entropy".into(),
+            format!("{:.*}", plain_decimals(entropy, args), entropy),
+        ));
+    }
+
+    if let Some(level) = args.ci {
+        let (low, high) = stats.mean_ci(level);
+        left_items.push((
+            ci_label(level).into(),
+            format!("[{}, {}]", fmt(low), fmt(high)),
+        ));
+    }
+
+    if let Some(resamples) = args.bootstrap {
+        let level = args.ci.unwrap_or(DEFAULT_BOOTSTRAP_LEVEL);
+        let seed = args.seed.unwrap_or_else(|| fastrand::u64(..));
+        let statistic = args.bootstrap_stat;
+        let (low, high) = stats.bootstrap_ci(level, resamples, seed, |s| statistic.value(s, args));
+        left_items.push(("boot CI".into(), format!("[{}, {}]", fmt(low), fmt(high))));
+    }
+
+    if let Some(statistic) = args.jackknife {
+        let jackknife = stats.jackknife(statistic);
+        left_items.push(("jk bias".into(), fmt(jackknife.bias)));
+        left_items.push(("jk SE".into(), fmt(jackknife.std_error)));
+    }
+
+    let percentiles = [
+        (0.0, "min"),
+        (0.01, "1%ile"),
+        (0.05, "5%ile"),
+        (0.25, "25%ile"),
+        (0.50, "median"),
+        (0.75, "75%ile"),
+        (0.95, "95%ile"),
+        (0.99, "99%ile"),
+        (1.0, "max"),
+    ];
+
+    // A selection replaces the percentile column too
+    let right_items: Vec<Row> = match args.stats {
+        Some(_) => Vec::new(),
+        None => percentiles
+            .iter()
+            .map(|&(q, label)| (label.into(), fmt(stats.quantile(q))))
+            .collect(),
+    };
+
+    (left_items, right_items)
+}
+
+/// The left column's rows above the ones added by flags like --ci
+fn default_rows(stats: &Stats, args: &Args, fmt: &impl Fn(f64) -> String) -> Vec<Row> {
+    let mut left_items = vec![
+        ("n".into(), stats.n.to_string()),
+        ("sum".into(), fmt(stats.sum)),
+    ];
+
+    if !args.robust {
+        left_items.push(("mean".into(), fmt(stats.mean)));
+        if let Some(fraction) = args.trim {
+            left_items.push(("tmean".into(), fmt(stats.trimmed_mean(fraction))));
+        }
+    }
+
+    if let Some(fraction) = args.winsorize {
+        left_items.push(("wmean".into(), fmt(stats.winsorized_mean(fraction))));
+        left_items.push(("wvar".into(), fmt(stats.winsorized_variance(fraction))));
+    }
+
+    if !stats.geo_mean.is_nan() || args.geomean_always {
+        // Formats like hex or durations would disguise NaN as a value (0x0, 0h0mNaNs)
+        let gmean = match stats.geo_mean {
+            gmean if gmean.is_nan() => "NaN".to_string(),
+            gmean => fmt(gmean),
+        };
+        left_items.push(("gmean".into(), gmean));
+        // A multiplicative factor, so it's dimensionless like cv
+        let gsd = match stats.geo_std_dev {
+            gsd if gsd.is_nan() => "NaN".to_string(),
+            gsd => format!("×{:.*}", plain_decimals(gsd, args), gsd),
+        };
+        left_items.push(("gsd".into(), gsd));
+    }
+
+    if !stats.harmonic_mean.is_nan() {
+        left_items.push(("hmean".into(), fmt(stats.harmonic_mean)));
+    }
+
+    left_items.push((
+        "mode".into(),
+        fmt(build_kde(stats, args).mode(MODE_RESOLUTION)),
+    ));
+
+    if args.robust {
+        let fraction = args.trim.unwrap_or(ROBUST_TRIM);
+        left_items.push(("tmean".into(), fmt(stats.trimmed_mean(fraction))));
+        left_items.push(("MAD".into(), fmt(stats.mad())));
+        left_items.push(("IQR".into(), fmt(stats.iqr())));
+        left_items.push((
+            "outliers".into(),
+            stats.outlier_count(args.outliers_k).to_string(),
+        ));
+    } else {
+        left_items.push(("std dev".into(), fmt(stats.std_dev)));
+        left_items.push(("sem".into(), fmt(stats.sem())));
+        left_items.push(("variance".into(), fmt(stats.variance)));
+
+        // Dimensionless, so it ignores the selected format
+        let cv = stats.cv();
+        if !cv.is_nan() {
+            left_items.push(("cv".into(), format!("{:.2}%", cv * 100.0)));
+        }
+    }
+
+    left_items
+}
+
+/// Rows for the statistics named by --stats, in order, followed by any --trim and
+/// --winsorize rows
+fn selected_rows(
+    selection: &[Statistic],
+    stats: &Stats,
+    args: &Args,
+    fmt: &impl Fn(f64) -> String,
+) -> Vec<Row> {
+    let mut rows: Vec<Row> = selection
+        .iter()
+        .map(|statistic| {
+            let value = match statistic {
+                Statistic::N => stats.n.to_string(),
+                // Dimensionless, as in the default table
+                Statistic::Cv => format!("{:.2}%", stats.cv() * 100.0),
+                _ => fmt(statistic.value(stats, args)),
+            };
+            (statistic.label(), value)
+        })
+        .collect();
+
+    if let Some(fraction) = args.trim {
+        rows.push(("tmean".into(), fmt(stats.trimmed_mean(fraction))));
+    }
+    if let Some(fraction) = args.winsorize {
+        rows.push(("wmean".into(), fmt(stats.winsorized_mean(fraction))));
+        rows.push(("wvar".into(), fmt(stats.winsorized_variance(fraction))));
+    }
+
+    rows
+}
+
+/// Values in input order, each paired with the input (an index into `--inputs`) and the
+/// line it came from, for reporting where values occurred after sorting (--with-index)
+struct Indexed {
+    values: Vec<f64>,
+    positions: Vec<(usize, usize)>,
+}
+
+impl Indexed {
+    fn push(&mut self, input: usize, values: impl IntoIterator<Item = (f64, usize)>) {
+        for (value, line) in values {
+            self.values.push(value);
+            self.positions.push((input, line));
+        }
+    }
+
+    fn keep_last(&mut self, n: usize) {
+        let start = self.values.len().saturating_sub(n);
+        self.values.drain(..start);
+        self.positions.drain(..start);
+    }
+
+    /// Where the first occurrence of `value` came from
+    fn locate(&self, value: f64) -> Option<(usize, usize)> {
+        let i = self.values.iter().position(|&x| x == value)?;
+        Some(self.positions[i])
+    }
+}
+
+/// Rows giving the positions of the min and max: `line N` for a single input, or
+/// `path:N` when there are several. Binary input counts values instead of lines.
+fn position_rows(indexed: &Indexed, stats: &Stats, args: &Args, opts: &ParseOptions) -> Vec<Row> {
+    let describe = |(input, line): (usize, usize)| match args.inputs.get(input) {
+        Some(path) if args.inputs.len() > 1 => format!("{}:{}", path.display(), line),
+        _ if opts.binary => format!("value {}", line),
+        _ => format!("line {}", line),
+    };
+
+    [
+        ("min at", stats.quantile(0.0)),
+        ("max at", stats.quantile(1.0)),
+    ]
+    .into_iter()
+    .filter_map(|(label, value)| Some((label.into(), describe(indexed.locate(value)?))))
+    .collect()
+}
+
+/// The rows as the table shows them, with decimal points lined up unless --align says not to
+fn aligned(rows: &[Row], format: Format, args: &Args) -> Vec<Row> {
+    if args.align.decimal(format) {
+        align_decimals(rows)
+    } else {
+        rows.to_vec()
+    }
+}
+
+/// Pads values on the left so their decimal points line up. Values that don't start with a
+/// number, like "×1.23" or an interval, are left alone.
+fn align_decimals(rows: &[Row]) -> Vec<Row> {
+    let widths: Vec<Option<usize>> = rows.iter().map(|(_, value)| integer_width(value)).collect();
+    let widest = widths.iter().flatten().copied().max().unwrap_or(0);
+    rows.iter()
+        .zip(widths)
+        .map(|((label, value), width)| match width {
+            Some(width) => (
+                label.clone(),
+                format!("{}{}", " ".repeat(widest - width), value),
+            ),
+            None => (label.clone(), value.clone()),
+        })
+        .collect()
+}
+
+/// Characters before a value's decimal point, counting compound parts like the "8m" of
+/// "8m20.50s". A whole number's point would come after its leading digits (and thousands
+/// separators). None if the value doesn't start with a number.
+fn integer_width(value: &str) -> Option<usize> {
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let point = unsigned.find('.').unwrap_or_else(|| {
+        unsigned
+            .find(|c: char| !(c.is_ascii_digit() || c == ','))
+            .unwrap_or(unsigned.len())
+    });
+    Some(value.len() - unsigned.len() + unsigned[..point].chars().count())
+}
+
+/// Prints --output-format list: aligned like a table column, or joined by --field-sep
+fn print_list(rows: &[Row], format: Format, args: &Args) {
+    match &args.field_sep {
+        Some(sep) => print!("{}", render_list(rows, Some(sep))),
+        None => print!("{}", render_list(&aligned(rows, format, args), None)),
+    }
+}
+
+/// One `label value` row per line, joined by `sep`, or with labels right-aligned as in the
+/// table when there's none
+fn render_list(rows: &[Row], sep: Option<&str>) -> String {
+    rows.iter()
+        .map(|(label, value)| match sep {
+            Some(sep) => format!("{}{}{}\n", label, sep, value),
+            None => format!("{:>8}  {}\n", label, value),
+        })
+        .collect()
+}
+
+/// Lays out the two columns side by side, optionally with ANSI styling.
+/// Padding is applied before styling so escape codes don't skew the alignment.
+fn render_table(left_items: &[Row], right_items: &[Row], color: bool) -> String {
+    let paint = |text: String, style: Option<&str>| match style {
+        Some(code) if color => format!("\x1b[{}m{}\x1b[0m", code, text),
+        _ => text,
+    };
+    let label_style = Some(ANSI_DIM);
+
+    let max_rows = left_items.len().max(right_items.len());
+    let mut out = String::new();
+
+    for i in 0..max_rows {
+        if let Some((label, value)) = left_items.get(i) {
+            out += &paint(format!("{:>8}", label), label_style);
+            out += "  ";
+            // Padding only lines up the right column, so a lone column isn't padded
+            let value = if right_items.is_empty() {
+                value.clone()
+            } else {
+                format!("{:<20}", value)
+            };
+            out += &paint(value, value_style(label));
+        } else {
+            out += &format!("{:30}", "");
+        }
+
+        if let Some((label, value)) = right_items.get(i) {
+            out += &paint(format!("{:>8}", label), label_style);
+            out += "  ";
+            out += &paint(value.clone(), value_style(label));
+        }
+        out += "\n";
+    }
+
+    out
+}
+
+/// Significance level for the baseline comparison verdict
+const SIGNIFICANCE: f64 = 0.05;
+
+/// One line saying whether the inputs differ from the baseline, with the test's numbers
+fn comparison_verdict(current: &Stats, baseline: &Stats) -> String {
+    let test = mann_whitney(current, baseline);
+    let verdict = if test.p_value < SIGNIFICANCE {
+        "distributions differ"
+    } else {
+        "no significant difference"
+    };
+    format!(
+        "vs baseline: {} (Mann-Whitney U = {}, z = {:.2}, p = {:.4})",
+        verdict, test.u, test.z, test.p_value
+    )
+}
+
+/// How many outlying values to list as examples
+const OUTLIER_EXAMPLES: usize = 5;
+
+/// Count, share, and the most extreme few of the values outside the Tukey fences
+fn outlier_report(stats: &Stats, format: Format, args: &Args) -> String {
+    let (low, high) = stats.outlier_fences(args.outliers_k);
+    let (below, above) = stats.outliers(args.outliers_k);
+    let count = below.len() + above.len();
+    let show = |v: f64| display_value(v, format, None, args);
+
+    let mut report = format!(
+        "outliers: {} ({:.2}%) outside [{}, {}]\n",
+        count,
+        100.0 * count as f64 / stats.n as f64,
+        show(low),
+        show(high)
+    );
+    if count > 0 {
+        let examples: Vec<String> = below
+            .iter()
+            .chain(above.iter().rev())
+            .take(OUTLIER_EXAMPLES)
+            .map(|&v| show(v))
+            .collect();
+        let more = if count > OUTLIER_EXAMPLES {
+            ", ..."
+        } else {
+            ""
+        };
+        report += &format!("    e.g. {}{}\n", examples.join(", "), more);
+    }
+
+    report
+}
+
+/// Same two-column layout as the terminal table, as Markdown rows
+fn render_markdown(left_items: &[Row], right_items: &[Row]) -> String {
+    let cell = |items: &[Row], i: usize| {
+        items
+            .get(i)
+            .map(|(label, value)| format!("{} | {}", label, value))
+            .unwrap_or_else(|| " | ".to_string())
+    };
+
+    // A lone column (e.g. --count-only) doesn't need an empty percentile column
+    if right_items.is_empty() {
+        let mut out = String::from("| stat | value |\n|---|--:|\n");
+        for i in 0..left_items.len() {
+            out += &format!("| {} |\n", cell(left_items, i));
+        }
+        return out;
+    }
+
+    let mut out = String::from("| stat | value | percentile | value |\n|---|--:|---|--:|\n");
+    for i in 0..left_items.len().max(right_items.len()) {
+        out += &format!("| {} | {} |\n", cell(left_items, i), cell(right_items, i));
+    }
+
+    out
+}
+
+const ANSI_DIM: &str = "2";
+const ANSI_BOLD: &str = "1";
+const ANSI_BOLD_YELLOW: &str = "1;33";
+
+/// Central tendency stands out; the tail, where problems usually hide, stands out most
+fn value_style(label: &str) -> Option<&'static str> {
+    match label {
+        "mean" | "median" => Some(ANSI_BOLD),
+        "99%ile" | "max" => Some(ANSI_BOLD_YELLOW),
+        _ => None,
+    }
+}
+
+/// Auto enables color only on a terminal, and `NO_COLOR` (https://no-color.org) turns it off
+fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+    }
+}
+
+/// The plotted x range: the KDE's padded bounds, from the `--tail` quantile up, or the
+/// `--plot-range` quantiles padded like the bounds (but never past them)
+fn plot_window(stats: &Stats, kde: &KDE, args: &Args) -> (f64, f64) {
+    let (min_x, max_x) = kde.bounds();
+    if let Some(p) = args.plot_range {
+        let (low, high) = (stats.quantile(p), stats.quantile(1.0 - p));
+        if high > low {
+            let padding = (high - low) * 0.1;
+            return ((low - padding).max(min_x), (high + padding).min(max_x));
+        }
+    }
+    match args.tail {
+        Some(q) => (stats.quantile(q), max_x),
+        None => (min_x, max_x),
+    }
+}
+
+fn build_kde<'a>(stats: &'a Stats, args: &Args) -> KDE<'a> {
+    let kde = if args.robust_bandwidth {
+        KDE::robust(&stats.data, stats.iqr())
+    } else {
+        KDE::new(&stats.data)
+    };
+    if args.adaptive {
+        kde.into_adaptive()
+    } else {
+        kde
+    }
+}
+
+/// The KDE evaluated at `count` evenly spaced points across the plot window, scaled for
+/// --y-axis: what `plot_kde` draws and --kde-out writes
+fn kde_points(stats: &Stats, args: &Args, count: usize) -> Vec<(f64, f64)> {
+    let kde = build_kde(stats, args);
+    let mut samples = kde.pdf_grid(plot_window(stats, &kde, args), count);
+    args.y_axis.rescale(&mut samples, stats.n);
+    samples
+}
+
+fn plot_kde(stats: &Stats, format: Format, args: &Args) {
+    const CHART_WIDTH: usize = 160;
+    let samples = kde_points(stats, args, CHART_WIDTH);
+    let (min_x, max_x) = (samples[0].0, samples[samples.len() - 1].0);
+    let y_axis = args.y_axis;
+
+    let (scale, label_formatter) = x_axis(max_x, format, args);
+
+    let points: Vec<(f32, f32)> = samples
+        .iter()
+        .map(|&(x, y)| ((x / scale) as f32, y as f32))
+        .collect();
+
+    let y_label_formatter = match y_axis {
+        YAxis::Density => LabelFormat::None,
+        YAxis::Normalized => LabelFormat::Custom(Box::new(|v: f32| format!("{:.2}", v))),
+        YAxis::Count => LabelFormat::Custom(Box::new(|v: f32| format!("{:.0}", v))),
+    };
+
+    // Each marker is a line from the x-axis up to the curve's peak
+    let markers = marker_values(stats, args);
+    let top = points.iter().map(|&(_, y)| y).fold(0.0, f32::max);
+    let marker_lines: Vec<[(f32, f32); 2]> = markers
+        .iter()
+        .filter(|&&(_, x)| (min_x..=max_x).contains(&x))
+        .map(|&(_, x)| [((x / scale) as f32, 0.0), ((x / scale) as f32, top)])
+        .collect();
+    let curve = Shape::Lines(&points);
+    let marker_shapes: Vec<Shape> = marker_lines.iter().map(|line| Shape::Lines(line)).collect();
+
+    let mut chart = Chart::new(160, 40, (min_x / scale) as f32, (max_x / scale) as f32);
+    let mut chart = chart.lineplot(&curve);
+    for shape in &marker_shapes {
+        chart = chart.lineplot(shape);
+    }
+    chart
+        .x_label_format(label_formatter)
+        .y_label_format(y_label_formatter)
+        .nice();
+
+    if !markers.is_empty() {
+        let fixed = fixed_unit(stats, format, args);
+        println!(
+            "{}",
+            marker_legend(&markers, (min_x, max_x), |x| display_value(
+                x, format, fixed, args
+            ))
+        );
+    }
+}
+
+/// The --markers percentiles as (label, value) pairs, in the order given
+fn marker_values(stats: &Stats, args: &Args) -> Vec<(Cow<'static, str>, f64)> {
+    args.markers
+        .iter()
+        .flatten()
+        .map(|marker| (marker.label(), marker.value(stats, args)))
+        .collect()
+}
+
+/// The line under the KDE plot giving each marker's value, since textplots draws the
+/// markers in the same style as the curve. Markers outside a --tail or --plot-range window
+/// aren't drawn.
+fn marker_legend(
+    markers: &[(Cow<str>, f64)],
+    (min_x, max_x): (f64, f64),
+    fmt: impl Fn(f64) -> String,
+) -> String {
+    let entries: Vec<String> = markers
+        .iter()
+        .map(|&(ref label, x)| {
+            let drawn = if (min_x..=max_x).contains(&x) {
+                ""
+            } else {
+                " (off chart)"
+            };
+            format!("{} = {}{}", label, fmt(x), drawn)
+        })
+        .collect();
+    format!("markers: {}", entries.join(", "))
+}
+
+fn plot_cdf(stats: &Stats, format: Format, args: &Args) {
+    let (min_x, max_x) = plot_window(stats, &KDE::new(&stats.data), args);
+    let (scale, label_formatter) = x_axis(max_x, format, args);
+    let min_y = args.tail.unwrap_or(0.0);
+
+    // With --plot-range the window can cut into the data at either end
+    let fraction_below = |x: f64| stats.data.partition_point(|&v| v < x) as f64 / stats.n as f64;
+    let fraction_through = |x: f64| stats.data.partition_point(|&v| v <= x) as f64 / stats.n as f64;
+    let start_y = args.tail.unwrap_or_else(|| fraction_below(min_x));
+
+    let steps = stats
+        .ecdf()
+        .into_iter()
+        .filter(|&(x, _)| (min_x..=max_x).contains(&x));
+    let points: Vec<(f32, f32)> = std::iter::once((min_x, start_y))
+        .chain(steps)
+        .chain(std::iter::once((max_x, fraction_through(max_x))))
+        .map(|(x, y)| ((x / scale) as f32, y as f32))
+        .collect();
+
+    Chart::new_with_y_range(
+        160,
+        40,
+        (min_x / scale) as f32,
+        (max_x / scale) as f32,
+        min_y as f32,
+        1.0,
+    )
+    .lineplot(&Shape::Lines(&points))
+    .x_label_format(label_formatter)
+    .y_label_format(LabelFormat::Custom(Box::new(|v: f32| format!("{:.2}", v))))
+    .nice();
+}
+
+fn plot_box(stats: &Stats, format: Format, args: &Args) {
+    let width = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(80);
+    print!(
+        "{}",
+        render_box(stats, width, args.outliers_k, |v| display_value(
+            v, format, None, args
+        ))
+    );
+}
+
+/// Draws a horizontal box plot spanning min to max over `width` columns, e.g.
+/// `|---[==|====]-----|   o`, with the min and max values labeled underneath
+fn render_box(stats: &Stats, width: usize, k: f64, label: impl Fn(f64) -> String) -> String {
+    let width = width.max(2);
+    let (min, max) = (stats.quantile(0.0), stats.quantile(1.0));
+    let column = |x: f64| {
+        let fraction = if max > min {
+            (x - min) / (max - min)
+        } else {
+            0.0
+        };
+        (fraction * (width - 1) as f64).round() as usize
+    };
+
+    // Whiskers reach the most extreme values still inside the fences. With a small k there
+    // may be none (e.g. k = 0 and no values between the quartiles), so they shrink to the box.
+    let (below, above) = stats.outliers(k);
+    let inside = &stats.data[below.len()..stats.n - above.len()];
+    let (low_whisker, high_whisker) = match (inside.first(), inside.last()) {
+        (Some(&low), Some(&high)) => (low, high),
+        _ => (stats.quantile(0.25), stats.quantile(0.75)),
+    };
+
+    let mut line = vec![' '; width];
+    line[column(low_whisker)..=column(high_whisker)].fill('-');
+    line[column(stats.quantile(0.25))..=column(stats.quantile(0.75))].fill('=');
+    line[column(low_whisker)] = '|';
+    line[column(high_whisker)] = '|';
+    line[column(stats.quantile(0.25))] = '[';
+    line[column(stats.quantile(0.75))] = ']';
+    line[column(stats.quantile(0.5))] = '|';
+    for &x in below.iter().chain(above) {
+        line[column(x)] = 'o';
+    }
+
+    let (min_label, max_label) = (label(min), label(max));
+    let gap = width.saturating_sub(min_label.chars().count() + max_label.chars().count());
+    format!(
+        "{}\n{}{}{}\n",
+        line.into_iter().collect::<String>(),
+        min_label,
+        " ".repeat(gap.max(1)),
+        max_label
+    )
+}
+
+/// Scale and tick labels for a chart's x-axis, in the display unit if one is set
+fn x_axis(max_x: f64, format: Format, args: &Args) -> (f64, LabelFormat) {
+    let (scale, unit_label) = match args.display_unit {
+        Some(unit) => (unit.scale(), unit.label()),
+        None => get_display_scale(max_x, format),
+    };
+
+    let label_formatter = if !unit_label.is_empty() {
+        let unit = unit_label.to_string();
+        LabelFormat::Custom(Box::new(move |v: f32| format!("{:.1}{}", v, unit)))
+    } else {
+        LabelFormat::Value
+    };
+
+    (scale, label_formatter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_path_gzip() {
+        use flate2::{Compression, write::GzEncoder};
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut encoder = GzEncoder::new(temp_file.reopen().unwrap(), Compression::default());
+        encoder.write_all(b"1\n2\n0x3\n").unwrap();
+        encoder.finish().unwrap();
+
+        let opts = ParseOptions::new(Some(Unit::Milliseconds));
+        assert_eq!(
+            read_path(temp_file.path(), &opts, false),
+            vec![1e6, 2e6, 3e6]
+        );
+    }
+
+    #[test]
+    fn test_read_path_plain() {
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"1\n2\n").unwrap();
+        temp_file.flush().unwrap();
+
+        let opts = ParseOptions::default();
+        assert_eq!(read_path(temp_file.path(), &opts, false), vec![1.0, 2.0]);
+    }
+
+    fn labels(rows: &[Row]) -> Vec<&str> {
+        rows.iter().map(|(label, _)| label.as_ref()).collect()
+    }
+
+    fn row(label: &'static str, value: &str) -> Row {
+        (label.into(), value.to_string())
+    }
+
+    #[test]
+    fn test_summary_columns_default() {
+        let args = Args::parse_from(["disty"]);
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+        let (left, right) = summary_columns(&stats, Format::Float, &args);
+
+        assert_eq!(
+            labels(&left),
+            vec![
+                "n", "sum", "mean", "gmean", "gsd", "hmean", "mode", "std dev", "sem", "variance",
+                "cv"
+            ]
+        );
+        assert!(labels(&right).contains(&"median"));
+    }
+
+    #[test]
+    fn test_summary_columns_robust() {
+        let args = Args::parse_from(["disty", "--robust"]);
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+        let (left, right) = summary_columns(&stats, Format::Float, &args);
+        let left_labels = labels(&left);
+
+        assert!(left_labels.contains(&"MAD"));
+        assert!(left_labels.contains(&"tmean"));
+        assert!(left_labels.contains(&"IQR"));
+        assert!(left_labels.contains(&"outliers"));
+        assert!(!left_labels.contains(&"mean"));
+        assert!(!left_labels.contains(&"std dev"));
+        assert!(!left_labels.contains(&"variance"));
+        assert!(labels(&right).contains(&"median"));
+
+        let outliers = left.iter().find(|(label, _)| *label == "outliers").unwrap();
+        assert_eq!(outliers.1, "1");
+    }
+
+    #[test]
+    fn test_uniq_c_stats() {
+        let opts = ParseOptions {
+            uniq_c: true,
+            ..Default::default()
+        };
+        let data = parsing::read_buffered(&b"   3 10\n   1 20"[..], &opts);
+        let stats = Stats::new(data);
+
+        assert_eq!(stats.data, vec![10.0, 10.0, 10.0, 20.0]);
+        assert_eq!(stats.mean, 12.5);
+    }
+
+    #[test]
+    fn test_display_value_byte_ambiguity() {
+        let args = Args::parse_from(["disty", "--byte-ambiguity"]);
+        assert_eq!(
+            display_value(1000.0, Format::Bytes, None, &args),
+            "1.00KB / 1000B"
+        );
+        assert_eq!(
+            display_value(1024.0 * 1024.0, Format::Bytes, None, &args),
+            "1.05MB / 1.00MiB"
+        );
+
+        // Only byte values are ambiguous
+        assert_eq!(display_value(1e6, Format::Time, None, &args), "1.00ms");
+
+        let args = Args::parse_from(["disty"]);
+        assert_eq!(display_value(1000.0, Format::Bytes, None, &args), "1000B");
+    }
+
+    #[test]
+    fn test_summary_columns_cv_ignores_format() {
+        let args = Args::parse_from(["disty"]);
+        let stats = Stats::new(vec![1e6, 3e6]);
+        let (left, _) = summary_columns(&stats, Format::Time, &args);
+
+        let cv = left.iter().find(|(label, _)| *label == "cv").unwrap();
+        assert_eq!(cv.1, "50.00%");
+    }
+
+    #[test]
+    fn test_display_value_fixed_unit() {
+        let stats = Stats::new(vec![2e4, 500e3, 2e9]);
+        let args = Args::parse_from(["disty", "--display-unit", "ms"]);
+        let fixed = fixed_unit(&stats, Format::Time, &args);
+        assert_eq!(display_value(500e3, Format::Time, fixed, &args), "0.50ms");
+        assert_eq!(display_value(2e9, Format::Time, fixed, &args), "2000.00ms");
+        assert_eq!(display_value(2e4, Format::Time, fixed, &args), "0.02ms");
+
+        let args = Args::parse_from(["disty", "--display-unit", "MiB"]);
+        let fixed = fixed_unit(&stats, Format::Bytes, &args);
+        assert_eq!(
+            display_value(512.0 * 1024.0, Format::Bytes, fixed, &args),
+            "0.50MiB"
+        );
+    }
+
+    #[test]
+    fn test_display_value_precision_auto() {
+        let args = Args::parse_from(["disty", "--precision-auto"]);
+        assert_eq!(
+            display_value(0.0000123, Format::Float, None, &args),
+            "0.0000123"
+        );
+        assert_eq!(display_value(12345.6, Format::Float, None, &args), "12346");
+        assert_eq!(
+            display_value(2e4, Format::Time, Some((1e6, "ms")), &args),
+            "0.0200ms"
+        );
+
+        let args = Args::parse_from(["disty", "--precision-auto", "--byte-ambiguity"]);
+        assert_eq!(
+            display_value(1_500_000.0, Format::Bytes, None, &args),
+            "1.50MB / 1.43MiB"
+        );
+        assert!(Args::try_parse_from(["disty", "--precision-auto", "--precision", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_summary_columns_relative_to() {
+        let args = Args::parse_from(["disty", "--relative-to", "4"]);
+        let stats = Stats::new(vec![2.0, 4.0, 6.0]);
+        let (left, right) = summary_columns(&stats, Format::Float, &args);
+
+        let row =
+            |items: &[Row], name: &str| items.iter().find(|(l, _)| *l == name).unwrap().1.clone();
+        assert_eq!(row(&left, "mean"), "1.00×");
+        assert_eq!(row(&right, "min"), "0.50×");
+        assert_eq!(row(&right, "max"), "1.50×");
+        // Dimensionless rows are left alone
+        assert_eq!(row(&left, "cv"), "40.82%");
+    }
+
+    #[test]
+    fn test_relative_to_rejects_zero() {
+        assert!(Args::try_parse_from(["disty", "--relative-to", "0"]).is_err());
+        assert!(Args::try_parse_from(["disty", "--relative-to", "-2"]).is_ok());
+    }
+
+    #[test]
+    fn test_show_units_consistent_time_suffix() {
+        let args = Args::parse_from(["disty", "--show-units"]);
+        let stats = Stats::new(vec![500e3, 1e9, 2e9]);
+        let (left, right) = summary_columns(&stats, Format::Time, &args);
+
+        for (label, value) in left.iter().chain(right.iter()) {
+            if ["n", "gsd", "cv"].contains(&label.as_ref()) {
+                continue;
+            }
+            assert!(
+                value.ends_with('s') && !value.ends_with("ms") && !value.ends_with("µs"),
+                "{} = {}",
+                label,
+                value
+            );
+        }
+        let min = right.iter().find(|(label, _)| *label == "min").unwrap();
+        assert_eq!(min.1, "0.00s");
+    }
+
+    #[test]
+    fn test_show_units_float_base_unit() {
+        let stats = Stats::new(vec![1e6, 2e6]);
+
+        let args = Args::parse_from(["disty", "--show-units", "-u", "ms", "-f", "float"]);
+        let fixed = fixed_unit(&stats, Format::Float, &args);
+        assert_eq!(
+            display_value(1e6, Format::Float, fixed, &args),
+            "1000000.00ns"
+        );
+
+        let args = Args::parse_from(["disty", "--show-units"]);
+        assert!(fixed_unit(&stats, Format::Float, &args).is_none());
+    }
+
+    #[test]
+    fn test_display_value_precision() {
+        let args = Args::parse_from(["disty", "--precision", "4"]);
+        assert_eq!(
+            display_value(1234.5678, Format::Time, None, &args),
+            "1.2346µs"
+        );
+        assert_eq!(display_value(0.5, Format::Float, None, &args), "0.5000");
+    }
+
+    #[test]
+    fn test_display_value_group_digits() {
+        let args = Args::parse_from(["disty", "--group-digits"]);
+        assert_eq!(
+            display_value(1001001.0, Format::Float, None, &args),
+            "1,001,001.00"
+        );
+        assert_eq!(display_value(5e6, Format::Time, None, &args), "5.00ms");
+
+        let args = Args::parse_from(["disty"]);
+        assert_eq!(
+            display_value(1001001.0, Format::Float, None, &args),
+            "1001001.00"
+        );
+    }
+
+    #[test]
+    fn test_render_table_plain() {
+        let left = vec![row("n", "3"), row("mean", "2.00")];
+        let right = vec![row("median", "2.00")];
+        let table = render_table(&left, &right, false);
+
+        assert!(!table.contains('\x1b'));
+        assert_eq!(
+            table,
+            "       n  3                     median  2.00\n    mean  2.00                \n"
+        );
+    }
+
+    #[test]
+    fn test_render_table_color() {
+        let left = vec![row("n", "3")];
+        let right = vec![row("99%ile", "9.00")];
+        let table = render_table(&left, &right, true);
+
+        assert!(table.contains("\x1b[2m       n\x1b[0m"));
+        assert!(table.contains("\x1b[1;33m9.00\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let left = vec![row("n", "3"), row("mean", "2.00")];
+        let right = vec![row("median", "2.00")];
+
+        assert_eq!(
+            render_markdown(&left, &right),
+            "| stat | value | percentile | value |\n\
+             |---|--:|---|--:|\n\
+             | n | 3 | median | 2.00 |\n\
+             | mean | 2.00 |  |  |\n"
+        );
+    }
+
+    #[test]
+    fn test_quiet_skips_plot() {
+        assert!(Args::parse_from(["disty", "--quiet"]).no_plot);
+        assert!(Args::parse_from(["disty", "-q"]).no_plot);
+        assert!(!Args::parse_from(["disty"]).no_plot);
+    }
+
+    #[test]
+    fn test_scale_combines_with_unit() {
+        let args = Args::parse_from(["disty", "--scale", "4096"]);
+        assert_eq!(parse_options(&args).scale, 4096.0);
+
+        let args = Args::parse_from(["disty", "-u", "us", "--scale", "2.5"]);
+        assert_eq!(parse_options(&args).scale, 2500.0);
+        assert_eq!(parse_options(&args).custom_scale, 2.5);
+    }
+
+    #[test]
+    fn test_parse_scale_rejects_invalid() {
+        assert_eq!(parse_scale("0.3125"), Ok(0.3125));
+        assert!(parse_scale("0").is_err());
+        assert!(parse_scale("-2").is_err());
+        assert!(parse_scale("inf").is_err());
+        assert!(parse_scale("NaN").is_err());
+        assert!(parse_scale("fast").is_err());
+    }
+
+    #[test]
+    fn test_render_box() {
+        // q25 = 3.5, median = 6, q75 = 8.5, upper fence = 16: 40 is an outlier
+        let stats = Stats::new(vec![
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 40.0,
+        ]);
+        let rendered = render_box(&stats, 40, TUKEY_K, |v| format!("{:.0}", v));
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "|--[=|==]|                             o");
+        assert_eq!(lines[1], format!("1{}40", " ".repeat(37)));
+    }
+
+    #[test]
+    fn test_render_box_no_outliers() {
+        let stats = Stats::new((0..=10).map(f64::from).collect());
+        let rendered = render_box(&stats, 11, TUKEY_K, |v| format!("{:.0}", v));
+
+        assert_eq!(rendered.lines().next().unwrap(), "|--[=|==]-|");
+    }
+
+    #[test]
+    fn test_render_box_nothing_inside_fences() {
+        // With k = 0 both values lie outside [q25, q75], so the whiskers collapse to the box
+        let stats = Stats::new(vec![0.0, 10.0]);
+        let rendered = render_box(&stats, 11, 0.0, |v| format!("{:.0}", v));
+
+        assert_eq!(rendered.lines().next().unwrap(), "o  [=|==] o");
+    }
+
+    #[test]
+    fn test_summary_columns_trim() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+
+        let args = Args::parse_from(["disty", "--trim", "0.2"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+        assert_eq!(&labels(&left)[..4], ["n", "sum", "mean", "tmean"]);
+        assert_eq!(left[3].1, "3.00");
+
+        let args = Args::parse_from(["disty", "--trim", "0"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+        assert_eq!(left[3].1, left[2].1);
+
+        assert!(Args::try_parse_from(["disty", "--trim", "0.5"]).is_err());
+        assert!(Args::try_parse_from(["disty", "--trim=-0.1"]).is_err());
+    }
+
+    #[test]
+    fn test_summary_columns_winsorize() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+        let args = Args::parse_from(["disty", "--winsorize", "0.25"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+
+        let left_labels = labels(&left);
+        assert_eq!(&left_labels[2..5], ["mean", "wmean", "wvar"]);
+        // Clamped to [2, 4]: 2, 2, 3, 4, 4
+        assert_eq!(left[3].1, "3.00");
+        assert_eq!(left[4].1, "0.80");
+    }
+
+    #[test]
+    fn test_parse_statistic() {
+        assert_eq!("mean".parse(), Ok(Statistic::Mean));
+        assert_eq!("std_dev".parse(), Ok(Statistic::StdDev));
+        assert_eq!("median".parse(), Ok(Statistic::Quantile(0.5)));
+        assert_eq!("p99".parse(), Ok(Statistic::Quantile(0.99)));
+        assert!(matches!("p99.9".parse(), Ok(Statistic::Quantile(q)) if (q - 0.999).abs() < 1e-12));
+        assert!("p101".parse::<Statistic>().is_err());
+        assert!("pfast".parse::<Statistic>().is_err());
+        assert!("average".parse::<Statistic>().is_err());
+        assert!(Args::try_parse_from(["disty", "--only", "average"]).is_err());
+    }
+
+    #[test]
+    fn test_statistic_value() {
+        let stats = Stats::new((1..=100).map(f64::from).collect());
+        let args = Args::parse_from(["disty"]);
+        assert_eq!(Statistic::N.value(&stats, &args), 100.0);
+        assert_eq!(Statistic::Mean.value(&stats, &args), 50.5);
+        assert_eq!(Statistic::Quantile(1.0).value(&stats, &args), 100.0);
+        assert!((Statistic::Quantile(0.99).value(&stats, &args) - 99.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_statistic_mode_follows_kde_flags() {
+        let mut data: Vec<f64> = (0..200)
+            .map(|i| (i % 20) as f64 + (i / 20) as f64 * 0.1)
+            .collect();
+        data.extend([80.0, 95.0, 120.0]);
+        let stats = Stats::new(data);
+
+        for flags in [
+            &["disty"][..],
+            &["disty", "--adaptive"],
+            &["disty", "--robust-bandwidth"],
+        ] {
+            let args = Args::parse_from(flags);
+            let table = build_kde(&stats, &args).mode(MODE_RESOLUTION);
+            assert_eq!(Statistic::Mode.value(&stats, &args), table, "{flags:?}");
+        }
+    }
+
+    #[test]
+    fn test_statistic_label() {
+        assert_eq!(Statistic::StdDev.label(), "std dev");
+        assert_eq!(Statistic::Quantile(0.0).label(), "min");
+        assert_eq!("p99".parse::<Statistic>().unwrap().label(), "99%ile");
+        assert_eq!("p99.9".parse::<Statistic>().unwrap().label(), "99.9%ile");
+    }
+
+    #[test]
+    fn test_summary_columns_stats_selection() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let args = Args::parse_from(["disty", "--stats", "p99,n,cv", "--ci", "0.95"]);
+        let (left, right) = summary_columns(&stats, Format::Float, &args);
+
+        assert_eq!(labels(&left), ["99%ile", "n", "cv", "95% CI"]);
+        assert_eq!(left[0].1, "4.96");
+        assert_eq!(left[1].1, "5");
+        assert_eq!(left[2].1, "47.14%");
+        assert!(right.is_empty());
+
+        assert!(Args::try_parse_from(["disty", "--stats", "mean,average"]).is_err());
+        assert!(Args::try_parse_from(["disty", "--stats", "mean", "--robust"]).is_err());
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        assert!(dimension_mismatch(Unit::Milliseconds, Format::Bytes));
+        assert!(dimension_mismatch(Unit::Mebibytes, Format::Time));
+        assert!(dimension_mismatch(Unit::Bytes, Format::Bits));
+        assert!(!dimension_mismatch(Unit::Kilobytes, Format::Bytes));
+        assert!(!dimension_mismatch(Unit::Mebibytes, Format::BytesSi));
+        assert!(!dimension_mismatch(Unit::Seconds, Format::Float));
+        assert!(!dimension_mismatch(Unit::Seconds, Format::Rate));
+    }
+
+    #[test]
+    fn test_unit_warning() {
+        let fractions = Stats::new(vec![0.002, 0.013, 0.5]);
+        assert!(
+            unit_warning(&fractions, Unit::Bytes)
+                .unwrap()
+                .contains("bytes")
+        );
+        assert!(
+            unit_warning(&fractions, Unit::Bits)
+                .unwrap()
+                .contains("bits")
+        );
+        assert_eq!(unit_warning(&fractions, Unit::Seconds), None);
+
+        // One whole count is enough to look like sizes
+        let sizes = Stats::new(vec![0.5, 4096.0]);
+        assert_eq!(unit_warning(&sizes, Unit::Bytes), None);
+    }
+
+    #[test]
+    fn test_align_decimals() {
+        let rows = vec![
+            row("n", "1000"),
+            row("mean", "5.25ms"),
+            row("sum", "-1,234.50"),
+            row("gsd", "×1.10"),
+        ];
+        let aligned = align_decimals(&rows);
+        let values: Vec<&str> = aligned.iter().map(|(_, value)| value.as_str()).collect();
+        assert_eq!(values, ["  1000", "     5.25ms", "-1,234.50", "×1.10"]);
+
+        assert_eq!(integer_width("NaN"), None);
+        assert_eq!(integer_width("1.5e-9"), Some(1));
+        assert_eq!(integer_width("8m20.50s"), Some(4));
+        assert!(Align::Auto.decimal(Format::Float));
+        assert!(!Align::Auto.decimal(Format::Hex));
+        assert!(!Align::Left.decimal(Format::Float));
+    }
+
+    #[test]
+    fn test_parse_timing() {
+        let elapsed = Duration::from_millis(42);
+        assert_eq!(
+            parse_timing(1_000_000, None, elapsed),
+            "parsed 1,000,000 values in 42.00ms (23.8M/s)"
+        );
+        assert_eq!(
+            parse_timing(1_000_000, Some(7_888_897), elapsed),
+            "parsed 1,000,000 values in 42.00ms (23.8M/s, 187.8MB/s)"
+        );
+    }
+
+    #[test]
+    fn test_render_list() {
+        let rows = vec![row("n", "3"), row("std dev", "1.25")];
+        assert_eq!(render_list(&rows, None), "       n  3\n std dev  1.25\n");
+        assert_eq!(render_list(&rows, Some("=")), "n=3\nstd dev=1.25\n");
+
+        let args = Args::parse_from(["disty", "--output-format", "list", "--field-sep", "\\t"]);
+        assert_eq!(args.field_sep.as_deref(), Some("\t"));
+        assert!(Args::try_parse_from(["disty", "--field-sep", ""]).is_err());
+    }
+
+    #[test]
+    fn test_markers() {
+        let stats = Stats::new((1..=100).map(f64::from).collect());
+
+        let args = Args::parse_from(["disty", "--markers"]);
+        let markers = marker_values(&stats, &args);
+        assert_eq!(markers[0], ("median".into(), 50.5));
+        assert_eq!(markers[1].0, "95%ile");
+
+        let args = Args::parse_from(["disty", "--markers=p99,min"]);
+        let markers = marker_values(&stats, &args);
+        let legend = marker_legend(&markers, (50.0, 100.0), |x| format!("{:.1}", x));
+        assert_eq!(legend, "markers: 99%ile = 99.0, min = 1.0 (off chart)");
+
+        assert!(marker_values(&stats, &Args::parse_from(["disty"])).is_empty());
+        assert!(Args::try_parse_from(["disty", "--markers=mean"]).is_err());
+    }
+
+    #[test]
+    fn test_summary_columns_ci() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let args = Args::parse_from(["disty", "--ci", "0.95"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+        assert_eq!(left.last().unwrap(), &row("95% CI", "[1.04, 4.96]"));
+
+        let args = Args::parse_from(["disty", "--ci", "0.99"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+        assert_eq!(left.last().unwrap().0, "99% CI");
+
+        assert!(Args::try_parse_from(["disty", "--ci", "95"]).is_err());
+    }
+
+    #[test]
+    fn test_summary_columns_bootstrap() {
+        let stats = Stats::new((1..=50).map(f64::from).collect());
+        let args = Args::parse_from(["disty", "--bootstrap", "200", "--seed", "7"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+
+        let (label, value) = left.last().unwrap();
+        assert_eq!(label, "boot CI");
+        let (again, _) = summary_columns(&stats, Format::Float, &args);
+        assert_eq!(again.last().unwrap().1, *value);
+    }
+
+    #[test]
+    fn test_summary_columns_jackknife() {
+        let stats = Stats::new(vec![2.0, 4.0, 6.0, 8.0, 10.0]);
+        let args = Args::parse_from(["disty", "--jackknife", "mean"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+
+        let rows = &left[left.len() - 2..];
+        assert_eq!(rows[0], row("jk bias", "0.00"));
+        assert_eq!(rows[1], row("jk SE", "1.41"));
+        assert!(Args::try_parse_from(["disty", "--jackknife", "p99"]).is_err());
+    }
+
+    #[test]
+    fn test_comparison_verdict() {
+        let baseline = Stats::new((0..30).map(f64::from).collect());
+        let slower = Stats::new((0..30).map(|i| f64::from(i) + 20.0).collect());
+        let same = Stats::new((0..30).map(|i| f64::from(i) + 0.5).collect());
+
+        assert!(
+            comparison_verdict(&slower, &baseline).starts_with("vs baseline: distributions differ")
+        );
+        assert!(comparison_verdict(&same, &baseline).starts_with("vs baseline: no significant"));
+    }
+
+    #[test]
+    fn test_summary_columns_entropy() {
+        let stats = Stats::new((0..=100).map(f64::from).collect());
+        let args = Args::parse_from(["disty", "--entropy", "-f", "time"]);
+        let (left, _) = summary_columns(&stats, Format::Time, &args);
+
+        let (label, value) = left.last().unwrap();
+        assert_eq!(*label, "entropy");
+        assert!(value.parse::<f64>().is_ok());
+    }
+
+    #[test]
+    fn test_summary_columns_precision_auto_dimensionless_rows() {
+        // Entropy is about 11.5 nats here, so three significant figures leave one decimal
+        let stats = Stats::new((0..=100).map(|i| f64::from(i) * 1000.0).collect());
+        let args = Args::parse_from(["disty", "--entropy", "--precision-auto"]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+        let value =
+            |items: &[Row], name: &str| items.iter().find(|(l, _)| *l == name).unwrap().1.clone();
+        let entropy = value(&left, "entropy");
+        assert_eq!(entropy.split_once('.').unwrap().1.len(), 1, "{entropy}");
+
+        // A gsd in the thousands needs no decimals for three significant figures
+        let stats = Stats::new(vec![1.0, 1e6]);
+        let (left, _) = summary_columns(&stats, Format::Float, &args);
+        let gsd = value(&left, "gsd");
+        assert!(gsd.starts_with('×') && !gsd.contains('.'), "{gsd}");
+    }
+
+    #[test]
+    fn test_plot_window_tail() {
+        let stats = Stats::new((0..=100).map(f64::from).collect());
+        let kde = KDE::new(&stats.data);
+
+        let args = Args::parse_from(["disty"]);
+        assert_eq!(plot_window(&stats, &kde, &args), kde.bounds());
+
+        let args = Args::parse_from(["disty", "--tail", "0.9"]);
+        assert_eq!(plot_window(&stats, &kde, &args), (90.0, kde.bounds().1));
+
+        assert!(Args::try_parse_from(["disty", "--tail", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_plot_window_range() {
+        // One extreme value would otherwise stretch the axis to 1e6
+        let mut data: Vec<f64> = (0..1000).map(f64::from).collect();
+        data.push(1e6);
+        let stats = Stats::new(data);
+        let kde = KDE::new(&stats.data);
+
+        let args = Args::parse_from(["disty", "--plot-range=0.01"]);
+        let (low, high) = plot_window(&stats, &kde, &args);
+        assert_eq!(low, kde.bounds().0);
+        assert!(high < 1100.0, "{high}");
+
+        let args = Args::parse_from(["disty", "--plot-range"]);
+        assert_eq!(args.plot_range, Some(0.005));
+
+        // Nothing to fit when the quantiles coincide
+        let constant_tails = Stats::new(vec![5.0; 10].into_iter().chain([1.0, 9.0]).collect());
+        let kde = KDE::new(&constant_tails.data);
+        let args = Args::parse_from(["disty", "--plot-range=0.1"]);
+        assert_eq!(plot_window(&constant_tails, &kde, &args), kde.bounds());
+
+        assert!(Args::try_parse_from(["disty", "--plot-range=0.5"]).is_err());
+        assert!(Args::try_parse_from(["disty", "--plot-range", "--tail", "0.9"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_clip() {
+        assert_eq!(
+            "10,500".parse(),
+            Ok(Clip {
+                low: Some(ClipBound::Value(10.0)),
+                high: Some(ClipBound::Value(500.0)),
+            })
+        );
+        assert_eq!(
+            "p1,p99".parse(),
+            Ok(Clip {
+                low: Some(ClipBound::Percentile(1.0)),
+                high: Some(ClipBound::Percentile(99.0)),
+            })
+        );
+        assert_eq!(
+            "-5,".parse(),
+            Ok(Clip {
+                low: Some(ClipBound::Value(-5.0)),
+                high: None,
+            })
+        );
+        assert!("500".parse::<Clip>().is_err());
+        assert!("p101,".parse::<Clip>().is_err());
+        assert!("a,b".parse::<Clip>().is_err());
+    }
+
+    #[test]
+    fn test_clip_apply() {
+        let data: Vec<f64> = (1..=100).map(f64::from).collect();
+
+        let clip: Clip = "10,20".parse().unwrap();
+        let (stats, clipped) = clip.apply(Stats::new(data.clone()), 1.0);
+        assert_eq!((stats.n, clipped), (11, 89));
+        assert_eq!(stats.quantile(0.0), 10.0);
+
+        // Raw bounds are in the input unit
+        let (stats, _) = clip.apply(Stats::new(data.iter().map(|x| x * 1e3).collect()), 1e3);
+        assert_eq!(stats.n, 11);
+
+        let clip: Clip = ",p50".parse().unwrap();
+        let (stats, clipped) = clip.apply(Stats::new(data), 1.0);
+        assert_eq!((stats.n, clipped), (50, 50));
+    }
+
+    #[test]
+    fn test_outlier_report() {
+        let args = Args::parse_from(["disty", "--outliers"]);
+        let stats = Stats::new(vec![-5.0, 1.0, 2.0, 3.0, 4.0, 5.0, 7.0, 20.0]);
+
+        assert_eq!(
+            outlier_report(&stats, Format::Float, &args),
+            "outliers: 2 (25.00%) outside [-3.88, 11.12]\n    e.g. -5.00, 20.00\n"
+        );
+
+        assert!(Args::try_parse_from(["disty", "--outliers-k=-1"]).is_err());
+        let args = Args::parse_from(["disty", "--outliers", "--outliers-k", "10"]);
+        assert_eq!(
+            outlier_report(&stats, Format::Float, &args),
+            "outliers: 0 (0.00%) outside [-35.75, 43.00]\n"
+        );
+    }
+
+    #[test]
+    fn test_color_never() {
+        assert!(!use_color(ColorChoice::Never));
+        assert!(use_color(ColorChoice::Always));
+    }
+
+    #[test]
+    fn test_constant_message() {
+        let stats = Stats::new(vec![5e6; 4]);
+        let message = constant_message(&stats, Format::Time).unwrap();
+        assert_eq!(message, "all 4 values are identical: 5.00ms");
+        assert!(!message.contains("NaN"));
+    }
+
+    #[test]
+    fn test_constant_message_single_value() {
+        let stats = Stats::new(vec![42.0]);
+        assert_eq!(
+            constant_message(&stats, Format::Float).unwrap(),
+            "single value: 42.00"
+        );
+    }
+
+    #[test]
+    fn test_constant_message_varied_input() {
+        let stats = Stats::new(vec![1.0, 2.0]);
+        assert!(constant_message(&stats, Format::Float).is_none());
+    }
 }
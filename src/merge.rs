@@ -0,0 +1,116 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// K-way merge over individually sorted value streams.
+/// Holds one pending value per source, so memory is O(k) regardless of input size.
+/// Inputs that aren't sorted produce output that isn't either (like `sort -m`).
+pub struct MergeSorted<I: Iterator<Item = f64>> {
+    sources: Vec<I>,
+    heap: BinaryHeap<Head>,
+}
+
+/// Next pending value of a source, ordered so the max-heap yields the smallest value first
+struct Head {
+    value: f64,
+    source: usize,
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for a min-heap; ties go to the earlier source to keep the merge stable
+        other
+            .value
+            .total_cmp(&self.value)
+            .then_with(|| other.source.cmp(&self.source))
+    }
+}
+
+pub fn merge_sorted<I: Iterator<Item = f64>>(mut sources: Vec<I>) -> MergeSorted<I> {
+    let heap = sources
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(source, it)| it.next().map(|value| Head { value, source }))
+        .collect();
+
+    MergeSorted { sources, heap }
+}
+
+impl<I: Iterator<Item = f64>> Iterator for MergeSorted<I> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let Head { value, source } = self.heap.pop()?;
+        if let Some(next) = self.sources[source].next() {
+            self.heap.push(Head {
+                value: next,
+                source,
+            });
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_two_sources() {
+        let a = vec![1.0, 3.0, 5.0];
+        let b = vec![2.0, 4.0, 6.0];
+        let merged: Vec<f64> = merge_sorted(vec![a.into_iter(), b.into_iter()]).collect();
+        assert_eq!(merged, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_merge_uneven_and_empty_sources() {
+        let sources = vec![
+            vec![].into_iter(),
+            vec![-1.0, 10.0, 10.0].into_iter(),
+            vec![0.0].into_iter(),
+        ];
+        let merged: Vec<f64> = merge_sorted(sources).collect();
+        assert_eq!(merged, vec![-1.0, 0.0, 10.0, 10.0]);
+    }
+
+    #[test]
+    fn test_merge_no_sources() {
+        let merged: Vec<f64> = merge_sorted(Vec::<std::vec::IntoIter<f64>>::new()).collect();
+        assert!(merged.is_empty());
+    }
+
+    #[test]
+    fn test_merge_sorted_files() {
+        use crate::parsing::{self, ParseOptions};
+        use std::io::{BufReader, Write};
+        use tempfile::NamedTempFile;
+
+        let mut first = NamedTempFile::new().unwrap();
+        writeln!(first, "1\n4\n9").unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        writeln!(second, "# sorted\n2\n3\n10").unwrap();
+
+        let opts = ParseOptions::default();
+        let sources = [&first, &second]
+            .iter()
+            .map(|f| parsing::values(BufReader::new(f.reopen().unwrap()), &opts))
+            .collect();
+        let merged: Vec<f64> = merge_sorted(sources).collect();
+
+        assert_eq!(merged, vec![1.0, 2.0, 3.0, 4.0, 9.0, 10.0]);
+    }
+}
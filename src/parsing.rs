@@ -1,209 +1,1773 @@
 use memmap2::Mmap;
 use rayon::prelude::*;
+use regex::bytes::Regex;
 use std::fs::File;
+use std::io::{BufRead, Read};
+use std::num::IntErrorKind;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::sampling::Reservoir;
+use crate::stats::Totals;
 use crate::units::Unit;
 
-/// Parses file using mmap.
+/// Controls how input lines are interpreted.
+#[derive(Clone)]
+pub struct ParseOptions {
+    /// Multiplier converting parsed values to base units
+    pub scale: f64,
+    /// Lines starting with this prefix (after leading whitespace) are ignored.
+    /// An empty prefix disables comment handling.
+    pub comment: Vec<u8>,
+    /// Parse the leading ASCII bytes of each line without requiring valid UTF-8
+    pub tolerant_utf8: bool,
+    /// Zero-based index of the delimited field to parse (whole line if None)
+    pub field: Option<usize>,
+    /// Byte separating fields when `field` is set
+    pub delimiter: u8,
+    /// Number of leading non-blank, non-comment lines to discard (e.g. CSV headers)
+    pub header_lines: usize,
+    /// Lines are `count value` pairs (as emitted by `uniq -c`); each value is repeated count times
+    pub uniq_c: bool,
+    /// Values may carry a unit suffix (e.g. "12ms", "4.5MiB"), which overrides the unit part
+    /// of `scale`
+    pub parse_suffix: bool,
+    /// The `--scale` factor already folded into `scale`, which suffixed values also get
+    pub custom_scale: f64,
+    /// Input is raw little-endian f64 values rather than text; only `scale` applies
+    pub binary: bool,
+    /// Lines are JSON objects; the value is read from this path of nested keys
+    pub json_path: Option<Vec<String>>,
+    /// Numbers use `,` as the decimal separator and `.` to group thousands (e.g. "1.000,5").
+    /// Applied after `field` extraction, so a `,` delimiter still splits columns first.
+    pub decimal_comma: bool,
+    /// Any run of ASCII whitespace separates values, not just line breaks, so a line may hold
+    /// many values (e.g. a one-line dump of space-separated hex). Comments still take up
+    /// whole lines, and `header_lines` still counts lines.
+    pub token_split: bool,
+    /// Only lines matching this pattern are parsed (or with `invert_filter`, only lines that
+    /// don't). Header lines are skipped before the filter applies. The regex runs on every
+    /// line, which can cost more than the parsing itself, so it's only set when asked for.
+    pub filter: Option<Regex>,
+    /// Drop the lines matching `filter` instead of keeping them
+    pub invert_filter: bool,
+    /// Count of 0x/0o/0b integers read with these options that exceeded 2^53 and so were
+    /// rounded to the nearest f64, see `take_inexact_integers`. Clones share the count.
+    pub inexact_integers: Arc<AtomicUsize>,
+    /// Count of 0x/0o/0b integers read with these options that didn't fit in 64 bits and so
+    /// were skipped, see `take_overflowed_integers`. Clones share the count.
+    pub overflowed_integers: Arc<AtomicUsize>,
+    /// The streaming readers skip lines that don't parse, as the mmap path always does,
+    /// rather than exiting. For files that have to be streamed (gzip, pipes), so they read
+    /// the same as any other file.
+    pub skip_invalid: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            scale: 1.0,
+            comment: b"#".to_vec(),
+            tolerant_utf8: false,
+            field: None,
+            delimiter: b',',
+            header_lines: 0,
+            uniq_c: false,
+            parse_suffix: false,
+            custom_scale: 1.0,
+            binary: false,
+            json_path: None,
+            decimal_comma: false,
+            token_split: false,
+            filter: None,
+            invert_filter: false,
+            inexact_integers: Arc::default(),
+            overflowed_integers: Arc::default(),
+            skip_invalid: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn new(unit: Option<Unit>) -> Self {
+        ParseOptions {
+            scale: unit.map(|u| u.scale()).unwrap_or(1.0),
+            ..Default::default()
+        }
+    }
+
+    /// A copy with `skip_invalid` set, sharing the integer counts
+    pub fn skipping_invalid(&self) -> Self {
+        ParseOptions {
+            skip_invalid: true,
+            ..self.clone()
+        }
+    }
+
+    /// How many 0x/0o/0b integers read since the last call exceeded 2^53 and so were rounded
+    /// to the nearest f64 (e.g. 0x20000000000001 becomes 0x20000000000000). Large addresses
+    /// then collide or shift slightly, so callers may want to warn. Resets the count.
+    pub fn take_inexact_integers(&self) -> usize {
+        self.inexact_integers.swap(0, Ordering::Relaxed)
+    }
+
+    /// How many 0x/0o/0b integers read since the last call were too large for a u64 and so
+    /// didn't parse, like any other invalid line. Resets the count.
+    pub fn take_overflowed_integers(&self) -> usize {
+        self.overflowed_integers.swap(0, Ordering::Relaxed)
+    }
+
+    /// Converts a parsed integer to f64, counting it when it can't be represented exactly
+    fn integer_to_f64(&self, v: u64) -> f64 {
+        if v > MAX_EXACT_INTEGER {
+            self.inexact_integers.fetch_add(1, Ordering::Relaxed);
+        }
+        v as f64
+    }
+
+    /// Whether an already-trimmed line is a comment
+    pub fn is_comment(&self, trimmed: &[u8]) -> bool {
+        !self.comment.is_empty() && trimmed.starts_with(&self.comment)
+    }
+
+    /// Whether a line passes `filter`
+    fn keeps(&self, line: &[u8]) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.is_match(line) != self.invert_filter)
+    }
+
+    /// Whether input may be cut inside a line, at whitespace: with `token_split`, unless a
+    /// `filter` has to see whole lines
+    fn splits_within_lines(&self) -> bool {
+        self.token_split && self.filter.is_none()
+    }
+
+    /// Whether input may be cut after a byte: a line break, or any whitespace when
+    /// `splits_within_lines`
+    fn is_separator(&self, byte: u8) -> bool {
+        byte == b'\n' || (self.splits_within_lines() && byte.is_ascii_whitespace())
+    }
+
+    /// Whether a line counts toward `header_lines`
+    fn is_content(&self, line: &[u8]) -> bool {
+        let trimmed = line.trim_ascii();
+        !trimmed.is_empty() && !self.is_comment(trimmed)
+    }
+}
+
+/// Errors surfaced by the `try_` readers instead of exiting the process.
+#[derive(Debug)]
+pub enum ReadError {
+    /// The file couldn't be memory-mapped (e.g. it's a directory or was truncated)
+    Map(std::io::Error),
+    /// A file that can't be mapped, like a FIFO, failed while being read instead
+    Io(std::io::Error),
+    /// Binary input whose length isn't a whole number of f64 values
+    PartialValue { trailing: usize },
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Map(e) => write!(f, "error mapping file: {}", e),
+            ReadError::Io(e) => write!(f, "error reading file: {}", e),
+            ReadError::PartialValue { trailing } => write!(
+                f,
+                "binary input ends with {} bytes that don't form a complete f64",
+                trailing
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Map(e) | ReadError::Io(e) => Some(e),
+            ReadError::PartialValue { .. } => None,
+        }
+    }
+}
+
+/// A file's contents, memory-mapped where possible
+enum Contents {
+    Mapped(Mmap),
+    /// A FIFO, character device, or other special file, which can't be mapped, read into
+    /// memory instead
+    Read(Vec<u8>),
+}
+
+impl std::ops::Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Contents::Mapped(mmap) => mmap,
+            Contents::Read(bytes) => bytes,
+        }
+    }
+}
+
+/// Maps a file, or reads it when it's neither a regular file nor a directory (which can't
+/// be read, so it's left to fail to map)
+fn map_file(file: &File) -> Result<Contents, ReadError> {
+    let special = file.metadata().is_ok_and(|m| !m.is_file() && !m.is_dir());
+    if special {
+        let mut bytes = Vec::new();
+        let mut reader = file;
+        reader.read_to_end(&mut bytes).map_err(ReadError::Io)?;
+        return Ok(Contents::Read(bytes));
+    }
+    let mmap = unsafe { Mmap::map(file).map_err(ReadError::Map)? };
+    Ok(Contents::Mapped(mmap))
+}
+
+/// Parses file using mmap, exiting the process on error.
 /// Much faster than sequential buffered I/O for large files.
-pub fn read_file_mmap(file: &File, unit: Option<Unit>) -> Vec<f64> {
-    let scale = unit.map(|u| u.scale()).unwrap_or(1.0);
+pub fn read_file_mmap(file: &File, opts: &ParseOptions) -> Vec<f64> {
+    try_read_file_mmap(file, opts).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Like `read_file_mmap`, but returns errors to the caller, for use as a library.
+pub fn try_read_file_mmap(file: &File, opts: &ParseOptions) -> Result<Vec<f64>, ReadError> {
+    read_mmap(file, opts, None)
+}
+
+/// Like `read_file_mmap`, but adds the number of bytes parsed so far to `progress` as the
+/// worker threads go, so another thread can report how far along a large file is.
+pub fn read_file_mmap_with_progress(
+    file: &File,
+    opts: &ParseOptions,
+    progress: &AtomicUsize,
+) -> Vec<f64> {
+    read_mmap(file, opts, Some(progress)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Bytes each worker parses between progress updates
+const PROGRESS_BLOCK: usize = 1 << 20;
+
+fn read_mmap(
+    file: &File,
+    opts: &ParseOptions,
+    progress: Option<&AtomicUsize>,
+) -> Result<Vec<f64>, ReadError> {
+    let mmap = map_file(file)?;
+    if opts.binary {
+        let values = decode_binary(&mmap, opts);
+        if let Some(progress) = progress {
+            progress.fetch_add(mmap.len(), Ordering::Relaxed);
+        }
+        return values;
+    }
+
+    // Headers are stripped before chunking, so chunk boundaries are only ever aligned within
+    // the body. A header can't straddle a chunk boundary, however large it is.
+    let header = header_end(&mmap, opts);
+    let body = &mmap[header..];
+    if let Some(progress) = progress {
+        progress.fetch_add(header, Ordering::Relaxed);
+    }
+
+    if body.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let results: Vec<Vec<f64>> = line_chunks(body, opts)
+        .par_iter()
+        .map(|&(start, end)| {
+            let chunk = &body[start..end];
+            match progress {
+                Some(progress) => parse_chunk_reporting(chunk, opts, progress),
+                None => parse_chunk(chunk, opts),
+            }
+        })
+        .collect();
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// Like `read_file_mmap`, but pairs each value with the 1-based line number it came from
+/// (for binary input, its 1-based position), so positions survive the values being sorted.
+pub fn read_file_mmap_indexed(file: &File, opts: &ParseOptions) -> Vec<(f64, usize)> {
+    try_read_file_mmap_indexed(file, opts).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Like `try_read_file_mmap`, with line numbers as in `read_file_mmap_indexed`. Each chunk
+/// numbers its lines from 0, then the chunks are offset by the lines before them, found
+/// by counting the newlines in the header and in each earlier chunk.
+pub fn try_read_file_mmap_indexed(
+    file: &File,
+    opts: &ParseOptions,
+) -> Result<Vec<(f64, usize)>, ReadError> {
+    let mmap = map_file(file)?;
+    if opts.binary {
+        let values = decode_binary(&mmap, opts)?;
+        return Ok(values.into_iter().zip(1..).collect());
+    }
+
+    let header = header_end(&mmap, opts);
+    let body = &mmap[header..];
+    let results: Vec<(Vec<(f64, usize)>, usize)> = line_chunks(body, opts)
+        .par_iter()
+        .map(|&(start, end)| {
+            let chunk = &body[start..end];
+            let mut values = Vec::new();
+            for_each_indexed_entry(chunk, opts, |value, count, index| {
+                values.extend(std::iter::repeat_n((value, index), count))
+            });
+            let lines = chunk.iter().filter(|&&b| b == b'\n').count();
+            (values, lines)
+        })
+        .collect();
+
+    let mut offset = 1 + mmap[..header].iter().filter(|&&b| b == b'\n').count();
+    let mut values = Vec::new();
+    for (chunk, lines) in results {
+        values.extend(
+            chunk
+                .into_iter()
+                .map(|(value, index)| (value, index + offset)),
+        );
+        offset += lines;
+    }
+    Ok(values)
+}
+
+/// Counts and sums a file's values without collecting them, exiting the process on error
+/// like `read_file_mmap`.
+pub fn total_file_mmap(file: &File, opts: &ParseOptions) -> Totals {
+    try_total_file_mmap(file, opts).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Like `try_read_file_mmap`, but each thread only accumulates its chunk's count and sum,
+/// which are then merged: no values are kept, so there's no allocation or sort.
+pub fn try_total_file_mmap(file: &File, opts: &ParseOptions) -> Result<Totals, ReadError> {
+    let mmap = map_file(file)?;
+    if opts.binary {
+        let trailing = mmap.len() % 8;
+        if trailing != 0 {
+            return Err(ReadError::PartialValue { trailing });
+        }
+        return Ok(mmap
+            .par_chunks_exact(8)
+            .fold(Totals::default, |mut totals, b| {
+                totals.push(f64::from_le_bytes(b.try_into().unwrap()) * opts.scale);
+                totals
+            })
+            .reduce(Totals::default, Totals::merge));
+    }
+
+    let body = &mmap[header_end(&mmap, opts)..];
+    Ok(line_chunks(body, opts)
+        .par_iter()
+        .map(|&(start, end)| {
+            let mut totals = Totals::default();
+            for_each_entry(&body[start..end], opts, |value, count| {
+                for _ in 0..count {
+                    totals.push(value);
+                }
+            });
+            totals
+        })
+        .reduce(Totals::default, Totals::merge))
+}
+
+/// Parses at most the first `limit` values of a file, exiting the process on error like
+/// `read_file_mmap`.
+pub fn read_file_mmap_head(file: &File, opts: &ParseOptions, limit: usize) -> Vec<f64> {
+    try_read_file_mmap_head(file, opts, limit).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Bytes per block when parsing only the head of a file
+const HEAD_BLOCK: usize = 1 << 16;
+
+/// Like `try_read_file_mmap`, but stops once `limit` values have been found. Splitting the
+/// whole file across threads would parse all of it, so instead the body is parsed from the
+/// front in rounds of one line-aligned block per thread: each round runs in parallel, and
+/// the rounds run in order until they've produced enough values. At most one round's worth
+/// of values past the limit is parsed and thrown away. Binary input only decodes the first
+/// `limit` values' bytes.
+pub fn try_read_file_mmap_head(
+    file: &File,
+    opts: &ParseOptions,
+    limit: usize,
+) -> Result<Vec<f64>, ReadError> {
+    let mmap = map_file(file)?;
+    if opts.binary {
+        let len = mmap.len().min(limit.saturating_mul(8));
+        return decode_binary(&mmap[..len], opts);
+    }
+
+    let mut body = &mmap[header_end(&mmap, opts)..];
+    let threads = rayon::current_num_threads();
+    let mut values = Vec::new();
+
+    while values.len() < limit && !body.is_empty() {
+        let mut blocks = Vec::with_capacity(threads);
+        while blocks.len() < threads && !body.is_empty() {
+            let end = block_end(body, HEAD_BLOCK, opts);
+            blocks.push(&body[..end]);
+            body = &body[end..];
+        }
+
+        let parsed: Vec<Vec<f64>> = blocks
+            .par_iter()
+            .map(|block| parse_chunk(block, opts))
+            .collect();
+        values.extend(parsed.into_iter().flatten());
+    }
+
+    values.truncate(limit);
+    Ok(values)
+}
+
+/// Samples up to `capacity` values from a file without collecting them all, exiting the
+/// process on error like `read_file_mmap`.
+pub fn sample_file_mmap(file: &File, opts: &ParseOptions, capacity: usize, seed: u64) -> Reservoir {
+    try_sample_file_mmap(file, opts, capacity, seed).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Like `try_read_file_mmap`, but each thread's chunk fills its own reservoir, and the
+/// reservoirs are merged into a uniform sample of the whole file. Each chunk gets an RNG
+/// derived from `seed`, so the sample doesn't depend on how rayon schedules the work.
+pub fn try_sample_file_mmap(
+    file: &File,
+    opts: &ParseOptions,
+    capacity: usize,
+    seed: u64,
+) -> Result<Reservoir, ReadError> {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut reservoir = Reservoir::new(capacity);
+
+    let mmap = map_file(file)?;
+    if opts.binary {
+        reservoir.extend(decode_binary(&mmap, opts)?, &mut rng);
+        return Ok(reservoir);
+    }
+
+    let body = &mmap[header_end(&mmap, opts)..];
+    let reservoirs: Vec<Reservoir> = line_chunks(body, opts)
+        .par_iter()
+        .enumerate()
+        .map(|(i, &(start, end))| {
+            let mut rng =
+                fastrand::Rng::with_seed(seed ^ (i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+            let mut reservoir = Reservoir::new(capacity);
+            for_each_entry(&body[start..end], opts, |value, count| {
+                for _ in 0..count {
+                    reservoir.push(value, &mut rng);
+                }
+            });
+            reservoir
+        })
+        .collect();
+
+    for chunk in reservoirs {
+        reservoir = reservoir.merge(chunk, &mut rng);
+    }
+    Ok(reservoir)
+}
+
+/// Splits `body` into one range per thread, with boundaries aligned to separators (see
+/// `next_boundary`) to avoid splitting numbers mid-parse
+fn line_chunks(body: &[u8], opts: &ParseOptions) -> Vec<(usize, usize)> {
+    if body.is_empty() {
+        return Vec::new();
+    }
+
+    let num_threads = rayon::current_num_threads();
+    let chunk_size = body.len().div_ceil(num_threads);
+
+    let mut boundaries = vec![0];
+    for i in 1..num_threads {
+        // A long line can carry the previous boundary past this chunk's nominal start
+        let pos = (i * chunk_size).max(boundaries[boundaries.len() - 1]);
+        if pos >= body.len() {
+            break;
+        }
+        let boundary = next_boundary(body, pos, opts);
+        if boundary < body.len() {
+            boundaries.push(boundary);
+        }
+    }
+    boundaries.push(body.len());
+
+    boundaries.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// How far `next_boundary` looks for a line break before settling for other whitespace
+const LINE_BREAK_WINDOW: usize = 4096;
+
+/// Offset just past the first separator at or after `from`, or the end of `data` if there
+/// isn't one. With `token_split`, a nearby line break is preferred over other whitespace,
+/// so a comment line is only cut in two if it's longer than `LINE_BREAK_WINDOW`. A `filter`
+/// keeps lines whole, since matching part of a line could give a different answer.
+fn next_boundary(data: &[u8], from: usize, opts: &ParseOptions) -> usize {
+    let rest = &data[from..];
+    let window = if opts.splits_within_lines() {
+        LINE_BREAK_WINDOW
+    } else {
+        rest.len()
+    };
+    let found = match rest.iter().take(window).position(|&b| b == b'\n') {
+        None if opts.splits_within_lines() => rest.iter().position(|&b| opts.is_separator(b)),
+        found => found,
+    };
+    found.map_or(data.len(), |i| from + i + 1)
+}
+
+/// Byte offset just past the header lines, found with a cheap sequential scan for newlines.
+/// Returns the data length if the input has no more than `header_lines` content lines.
+fn header_end(data: &[u8], opts: &ParseOptions) -> usize {
+    let mut remaining = opts.header_lines;
+    let mut start = 0;
+
+    while remaining > 0 && start < data.len() {
+        let end = data[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(data.len(), |i| start + i);
+        if opts.is_content(&data[start..end]) {
+            remaining -= 1;
+        }
+        start = end + 1;
+    }
+
+    start.min(data.len())
+}
+
+/// Incrementally parses a text file that's still being written to (e.g. a growing log).
+/// Each `read_appended` picks up whatever was written since the last call, holding back a
+/// trailing partial line until its newline arrives.
+pub struct Tail {
+    file: File,
+    pending: Vec<u8>,
+    header_remaining: usize,
+}
+
+impl Tail {
+    pub fn new(file: File, opts: &ParseOptions) -> Self {
+        Tail {
+            file,
+            pending: Vec::new(),
+            header_remaining: opts.header_lines,
+        }
+    }
+
+    /// Values from the complete lines (or with `token_split`, tokens) appended since the
+    /// last call
+    pub fn read_appended(&mut self, opts: &ParseOptions) -> std::io::Result<Vec<f64>> {
+        self.file.read_to_end(&mut self.pending)?;
+        let Some(end) = self.pending.iter().rposition(|&b| opts.is_separator(b)) else {
+            return Ok(Vec::new());
+        };
+        let lines: Vec<u8> = self.pending.drain(..=end).collect();
+
+        let mut body = &lines[..];
+        while self.header_remaining > 0 && !body.is_empty() {
+            let line_end = body.iter().position(|&b| b == b'\n').unwrap_or(body.len());
+            if opts.is_content(&body[..line_end]) {
+                self.header_remaining -= 1;
+            }
+            body = &body[(line_end + 1).min(body.len())..];
+        }
+
+        Ok(parse_chunk(body, opts))
+    }
+}
+
+/// Reads raw little-endian f64 values from a stream (e.g. stdin or a decompressor),
+/// exiting on error like `read_buffered`.
+pub fn read_binary(mut reader: impl Read, opts: &ParseOptions) -> Vec<f64> {
+    let mut bytes = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut bytes) {
+        eprintln!("error reading input: {}", e);
+        std::process::exit(1);
+    }
+    decode_binary(&bytes, opts).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Decodes packed little-endian f64 values. Each value is copied out of its 8 bytes, so the
+/// input needn't be aligned (mmaps are, but nothing guarantees it for arbitrary slices).
+fn decode_binary(bytes: &[u8], opts: &ParseOptions) -> Result<Vec<f64>, ReadError> {
+    let trailing = bytes.len() % 8;
+    if trailing != 0 {
+        return Err(ReadError::PartialValue { trailing });
+    }
+
+    Ok(bytes
+        .par_chunks_exact(8)
+        .map(|b| f64::from_le_bytes(b.try_into().unwrap()) * opts.scale)
+        .collect())
+}
+
+/// Parses newline-delimited numbers sequentially from a buffered reader (e.g. stdin).
+/// Unlike the mmap path, lines that fail to parse are treated as fatal errors (unless
+/// `skip_invalid` is set), though lines
+/// with nothing to parse, like those too short to have the `field` or without the
+/// `json_path` key, are still skipped.
+pub fn read_buffered(reader: impl BufRead, opts: &ParseOptions) -> Vec<f64> {
+    values(reader, opts).collect()
+}
+
+/// Lazily parses values from a buffered reader with the same rules as `read_buffered`.
+/// Used where inputs are streamed rather than collected (e.g. merging sorted files).
+pub fn values<'a>(
+    reader: impl BufRead + 'a,
+    opts: &'a ParseOptions,
+) -> impl Iterator<Item = f64> + 'a {
+    indexed_values(reader, opts).map(|(value, _)| value)
+}
+
+/// Like `values`, but pairs each value with its 1-based line number
+pub fn indexed_values<'a>(
+    reader: impl BufRead + 'a,
+    opts: &'a ParseOptions,
+) -> impl Iterator<Item = (f64, usize)> + 'a {
+    let mut header_remaining = opts.header_lines;
+
+    reader
+        .split(b'\n')
+        .zip(1..)
+        .flat_map(move |(line, number)| {
+            let line = line.unwrap_or_else(|e| {
+                eprintln!("error reading input: {}", e);
+                std::process::exit(1);
+            });
+
+            let mut entries = Vec::new();
+            let trimmed = strip_cr(&line).trim_ascii();
+            if trimmed.is_empty() || opts.is_comment(trimmed) {
+                return entries;
+            }
+            if header_remaining > 0 {
+                header_remaining -= 1;
+                return entries;
+            }
+            if !opts.keeps(trimmed) {
+                return entries;
+            }
+
+            let mut push = |entry: &[u8]| {
+                let (value, count) = match parse_entry(entry, opts) {
+                    Some(parsed) => parsed,
+                    // Nothing to parse, which the mmap path skips too
+                    None if opts.skip_invalid || lacks_selection(entry, opts) => return,
+                    None => {
+                        eprintln!("error parsing number '{}'", String::from_utf8_lossy(entry));
+                        std::process::exit(1);
+                    }
+                };
+                entries.extend(std::iter::repeat_n((value, number), count));
+            };
+            // With `token_split` each token is an entry, otherwise the whole line is
+            if opts.token_split {
+                tokens(trimmed).for_each(push);
+            } else {
+                push(trimmed);
+            }
+            entries
+        })
+}
+
+/// Parses newline-delimited numbers from byte slice.
+/// Returns values scaled to base units (ignores invalid lines silently).
+fn parse_chunk(chunk: &[u8], opts: &ParseOptions) -> Vec<f64> {
+    let mut values = Vec::new();
+    for_each_entry(chunk, opts, |value, count| {
+        values.extend(std::iter::repeat_n(value, count))
+    });
+    values
+}
+
+/// Like `parse_chunk`, but works through the chunk in line-aligned blocks of about
+/// `PROGRESS_BLOCK` bytes, adding each block's size to `progress` once it's parsed
+fn parse_chunk_reporting(chunk: &[u8], opts: &ParseOptions, progress: &AtomicUsize) -> Vec<f64> {
+    let mut values = Vec::new();
+    let mut rest = chunk;
+
+    while !rest.is_empty() {
+        let end = block_end(rest, PROGRESS_BLOCK, opts);
+        for_each_entry(&rest[..end], opts, |value, count| {
+            values.extend(std::iter::repeat_n(value, count))
+        });
+        progress.fetch_add(end, Ordering::Relaxed);
+        rest = &rest[end..];
+    }
+
+    values
+}
+
+/// Length of the first block of `rest` that's at least `size` bytes and ends on a separator
+/// (or at the end of `rest`)
+fn block_end(rest: &[u8], size: usize, opts: &ParseOptions) -> usize {
+    if size >= rest.len() {
+        return rest.len();
+    }
+    next_boundary(rest, size.max(1) - 1, opts)
+}
+
+/// Calls `f` with each valid line's value and repeat count, like `parse_chunk` without
+/// collecting the values
+fn for_each_entry(chunk: &[u8], opts: &ParseOptions, mut f: impl FnMut(f64, usize)) {
+    for_each_indexed_entry(chunk, opts, |value, count, _| f(value, count));
+}
+
+/// Like `for_each_entry`, but also passes each line's 0-based index within the chunk
+/// (blank, comment, and unparseable lines still count)
+fn for_each_indexed_entry(chunk: &[u8], opts: &ParseOptions, mut f: impl FnMut(f64, usize, usize)) {
+    let mut parse = |line: &[u8], index: usize| {
+        if !opts.keeps(line) {
+            return;
+        }
+        if opts.token_split {
+            if opts.is_comment(line.trim_ascii()) {
+                return;
+            }
+            for token in tokens(line) {
+                if let Some((value, count)) = parse_entry(token, opts) {
+                    f(value, count, index);
+                }
+            }
+        } else if let Some((value, count)) = parse_entry(strip_cr(line), opts) {
+            f(value, count, index);
+        }
+    };
+    let mut start = 0;
+    let mut index = 0;
+
+    for (i, &byte) in chunk.iter().enumerate() {
+        if byte == b'\n' {
+            if i > start {
+                parse(&chunk[start..i], index);
+            }
+            start = i + 1;
+            index += 1;
+        }
+    }
+
+    // Handle last line if no trailing newline
+    if start < chunk.len() {
+        parse(&chunk[start..], index);
+    }
+}
+
+/// The whitespace-separated tokens of a line, for `token_split`
+fn tokens(line: &[u8]) -> impl Iterator<Item = &[u8]> {
+    line.split(u8::is_ascii_whitespace)
+        .filter(|token| !token.is_empty())
+}
+
+/// Drops the `\r` left over from a CRLF line ending after splitting on `\n`, so parsing
+/// doesn't depend on whitespace trimming happening to remove it
+fn strip_cr(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Parses a line into a value and how many times it occurs (always once unless `uniq_c` is set).
+fn parse_entry(line: &[u8], opts: &ParseOptions) -> Option<(f64, usize)> {
+    if !opts.uniq_c {
+        return parse_line(line, opts).map(|value| (value, 1));
+    }
+
+    let trimmed = line.trim_ascii();
+    let split = trimmed.iter().position(|b| b.is_ascii_whitespace())?;
+    let count = std::str::from_utf8(&trimmed[..split])
+        .ok()?
+        .parse::<usize>()
+        .ok()?;
+    parse_line(&trimmed[split..], opts).map(|value| (value, count))
+}
+
+/// Whether an entry that didn't parse has no `field` or `json_path` value to read at all (a
+/// short line, an empty field, a line that isn't a JSON object, a missing key), as opposed
+/// to one that isn't a number
+fn lacks_selection(entry: &[u8], opts: &ParseOptions) -> bool {
+    let mut line = strip_cr(entry).trim_ascii();
+    if opts.uniq_c {
+        // The count's own errors aren't a missing selection; what follows it is the line
+        let split = line.iter().position(|b| b.is_ascii_whitespace());
+        line = split.map_or(&[][..], |split| line[split..].trim_ascii());
+    }
+
+    if let Some(path) = &opts.json_path {
+        let root = serde_json::from_slice::<serde_json::Value>(line).ok();
+        return root.is_none_or(|root| {
+            path.iter()
+                .try_fold(&root, |value, key| value.get(key))
+                .is_none()
+        });
+    }
+    opts.field.is_some_and(|field| {
+        extract_field(line, opts.delimiter, field).is_none_or(|value| value.trim_ascii().is_empty())
+    })
+}
+
+/// Parses a single line as either decimal float or an integer with a radix prefix (0x hex,
+/// 0o octal, or 0b binary).
+/// Returns None for invalid input rather than panicking (for robustness with untrusted input).
+/// Comment lines are skipped explicitly rather than relying on them failing to parse.
+fn parse_line(line: &[u8], opts: &ParseOptions) -> Option<f64> {
+    let line = strip_cr(line);
+    let mut start = 0;
+    let mut end = line.len();
+
+    while start < end && line[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while end > start && line[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+
+    if start == end {
+        return None;
+    }
+
+    let mut trimmed = &line[start..end];
+    if opts.is_comment(trimmed) {
+        return None;
+    }
+
+    if let Some(path) = &opts.json_path {
+        return json_value(trimmed, path).map(|v| v * opts.scale);
+    }
+
+    if let Some(field) = opts.field {
+        trimmed = extract_field(trimmed, opts.delimiter, field)?.trim_ascii();
+        if trimmed.is_empty() {
+            return None;
+        }
+    }
+
+    let s = if opts.tolerant_utf8 {
+        ascii_prefix(trimmed)
+    } else {
+        std::str::from_utf8(trimmed).ok()?
+    };
+
+    let normalized;
+    let s = if opts.decimal_comma {
+        normalized = s.replace('.', "").replace(',', ".");
+        normalized.as_str()
+    } else {
+        s
+    };
+
+    let radix = match s.as_bytes() {
+        [b'0', b'x' | b'X', ..] => 16,
+        [b'0', b'o' | b'O', ..] => 8,
+        [b'0', b'b' | b'B', ..] => 2,
+        _ => {
+            if opts.parse_suffix
+                && let Some((number, unit)) = split_suffix(s)
+            {
+                return number
+                    .parse::<f64>()
+                    .ok()
+                    .map(|v| v * unit.scale() * opts.custom_scale);
+            }
+            return s.parse::<f64>().ok().map(|v| v * opts.scale);
+        }
+    };
+    let digits = &s[2..];
+    if radix == 16 && digits.contains(['.', 'p', 'P']) {
+        return parse_hex_float(digits).map(|v| v * opts.scale);
+    }
+    match u64::from_str_radix(digits, radix) {
+        Ok(v) => Some(opts.integer_to_f64(v) * opts.scale),
+        // Past u64::MAX is an error rather than wrapping; count it so callers can say so
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+            opts.overflowed_integers.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Parses the digits of a hex float after its 0x, as in C's "1.8p3" (1.5 × 2³ = 12): a hex
+/// mantissa with an optional fraction and an optional binary exponent in decimal
+fn parse_hex_float(digits: &str) -> Option<f64> {
+    let (mantissa, exponent) = match digits.split_once(['p', 'P']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (digits, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+
+    let mut value = 0.0;
+    for c in whole.chars() {
+        value = value * 16.0 + f64::from(c.to_digit(16)?);
+    }
+    let mut place = 1.0 / 16.0;
+    for c in fraction.chars() {
+        value += f64::from(c.to_digit(16)?) * place;
+        place /= 16.0;
+    }
+    Some(value * 2f64.powi(exponent))
+}
+
+/// Integers up to 2^53 convert to f64 exactly; past it f64s are more than 1 apart
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+/// Splits "4.5MiB" or "12 ms" into the number and its unit, if the text after the last digit
+/// is a known unit suffix
+fn split_suffix(s: &str) -> Option<(&str, Unit)> {
+    let end = s.rfind(|c: char| c.is_ascii_digit() || c == '.')? + 1;
+    let (number, suffix) = s.split_at(end);
+    let unit = Unit::from_suffix(suffix.trim_start())?;
+    Some((number, unit))
+}
+
+/// Reads the number at `path` (e.g. ["a", "b"] for `{"a": {"b": 1}}`) from a JSON line.
+/// Non-JSON lines, missing keys, and non-numeric values give None.
+fn json_value(line: &[u8], path: &[String]) -> Option<f64> {
+    let root: serde_json::Value = serde_json::from_slice(line).ok()?;
+    path.iter()
+        .try_fold(&root, |value, key| value.get(key))?
+        .as_f64()
+}
+
+/// Returns the zero-based `index`th field of a delimited line, or None if the line is too short
+fn extract_field(line: &[u8], delimiter: u8, index: usize) -> Option<&[u8]> {
+    line.split(|&b| b == delimiter).nth(index)
+}
+
+/// Leading ASCII portion of a line, so numbers survive stray high bytes (e.g. Latin-1 text).
+/// Cuts at the first non-ASCII byte rather than the first non-digit to keep hex prefixes intact.
+fn ascii_prefix(bytes: &[u8]) -> &str {
+    let end = bytes
+        .iter()
+        .position(|b| !b.is_ascii())
+        .unwrap_or(bytes.len());
+    // ASCII is always valid UTF-8
+    std::str::from_utf8(&bytes[..end]).unwrap().trim_end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Unit;
+
+    fn scaled(scale: f64) -> ParseOptions {
+        ParseOptions {
+            scale,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_line_decimal() {
+        assert_eq!(parse_line(b"42.5", &ParseOptions::default()), Some(42.5));
+        assert_eq!(parse_line(b"100", &ParseOptions::default()), Some(100.0));
+        assert_eq!(parse_line(b"0", &ParseOptions::default()), Some(0.0));
+        assert_eq!(parse_line(b"-5.5", &ParseOptions::default()), Some(-5.5));
+    }
+
+    #[test]
+    fn test_parse_line_hex() {
+        assert_eq!(parse_line(b"0x10", &ParseOptions::default()), Some(16.0));
+        assert_eq!(parse_line(b"0xFF", &ParseOptions::default()), Some(255.0));
+        assert_eq!(parse_line(b"0x0", &ParseOptions::default()), Some(0.0));
+        assert_eq!(
+            parse_line(b"0xDEADBEEF", &ParseOptions::default()),
+            Some(3735928559.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_line_with_whitespace() {
+        assert_eq!(
+            parse_line(b"  42.5  ", &ParseOptions::default()),
+            Some(42.5)
+        );
+        assert_eq!(
+            parse_line(b"\t100\n", &ParseOptions::default()),
+            Some(100.0)
+        );
+        assert_eq!(
+            parse_line(b"  0x10  ", &ParseOptions::default()),
+            Some(16.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_line_with_scale() {
+        assert_eq!(parse_line(b"10", &scaled(2.0)), Some(20.0));
+        assert_eq!(parse_line(b"5.5", &scaled(1000.0)), Some(5500.0));
+        assert_eq!(parse_line(b"0x10", &scaled(10.0)), Some(160.0));
+    }
+
+    #[test]
+    fn test_parse_line_binary_and_octal() {
+        let opts = ParseOptions::default();
+        assert_eq!(parse_line(b"0b1010", &opts), Some(10.0));
+        assert_eq!(parse_line(b"0b0", &opts), Some(0.0));
+        assert_eq!(parse_line(b"0o17", &opts), Some(15.0));
+        assert_eq!(parse_line(b"0o777", &opts), Some(511.0));
+        assert_eq!(parse_line(b"0b102", &opts), None);
+        assert_eq!(parse_line(b"0o8", &opts), None);
+        assert_eq!(parse_line(b"0b", &opts), None);
+        assert_eq!(parse_line(b"0o17", &scaled(2.0)), Some(30.0));
+    }
+
+    #[test]
+    fn test_parse_line_prefixed_overflow() {
+        let opts = ParseOptions::default();
+        assert_eq!(
+            parse_line(b"0xFFFFFFFFFFFFFFFF", &opts),
+            Some(u64::MAX as f64)
+        );
+        assert_eq!(opts.take_overflowed_integers(), 0);
+
+        // One digit past u64::MAX fails rather than wrapping around, and is counted
+        assert_eq!(parse_line(b"0x10000000000000000", &opts), None);
+        let bits = format!("0b1{}", "0".repeat(64));
+        assert_eq!(parse_line(bits.as_bytes(), &opts), None);
+        assert_eq!(parse_line(b"0xnot_hex", &opts), None);
+        assert_eq!(opts.take_overflowed_integers(), 2);
+    }
+
+    #[test]
+    fn test_parse_line_uppercase_prefixes() {
+        let opts = ParseOptions::default();
+        assert_eq!(parse_line(b"0XFF", &opts), Some(255.0));
+        assert_eq!(parse_line(b"0B101", &opts), Some(5.0));
+        assert_eq!(parse_line(b"0O17", &opts), Some(15.0));
+    }
+
+    #[test]
+    fn test_parse_line_hex_float() {
+        let opts = ParseOptions::default();
+        assert_eq!(parse_line(b"0x1.8p3", &opts), Some(12.0));
+        assert_eq!(parse_line(b"0x1p-2", &opts), Some(0.25));
+        assert_eq!(parse_line(b"0X1P+4", &opts), Some(16.0));
+        assert_eq!(parse_line(b"0xa.8", &opts), Some(10.5));
+        assert_eq!(parse_line(b"0x.8p1", &opts), Some(1.0));
+        assert_eq!(parse_line(b"0x1.fffffffffffffp1023", &opts), Some(f64::MAX));
+        assert_eq!(parse_line(b"0x1.8p3", &scaled(2.0)), Some(24.0));
+
+        assert_eq!(parse_line(b"0x.p1", &opts), None);
+        assert_eq!(parse_line(b"0x1.8p", &opts), None);
+        assert_eq!(parse_line(b"0x1.gp1", &opts), None);
+        assert_eq!(parse_line(b"0x1.8p1.5", &opts), None);
+    }
+
+    #[test]
+    fn test_parse_line_precision_boundary() {
+        let opts = ParseOptions::default();
+        // 2^53 and everything below it convert exactly
+        assert_eq!(
+            parse_line(b"0x20000000000000", &opts),
+            Some(9007199254740992.0)
+        );
+        assert_eq!(
+            opts.integer_to_f64(MAX_EXACT_INTEGER - 1) as u64,
+            MAX_EXACT_INTEGER - 1
+        );
+        assert_eq!(opts.take_inexact_integers(), 0);
+
+        // 2^53 + 1 rounds to even, back down to 2^53, and is counted
+        assert_eq!(
+            parse_line(b"0x20000000000001", &opts),
+            Some(9007199254740992.0)
+        );
+        assert_eq!(opts.take_inexact_integers(), 1);
+        assert_eq!(opts.take_inexact_integers(), 0);
+    }
+
+    #[test]
+    fn test_read_counts_inexact_integers() {
+        let opts = ParseOptions::default();
+        let data = read_buffered(&b"0x20000000000001\n0x10\n0xffffffffffffffff\n"[..], &opts);
+        assert_eq!(data.len(), 3);
+        assert_eq!(opts.take_inexact_integers(), 2);
+    }
+
+    #[test]
+    fn test_parse_line_invalid() {
+        assert_eq!(parse_line(b"", &ParseOptions::default()), None);
+        assert_eq!(parse_line(b"   ", &ParseOptions::default()), None);
+        assert_eq!(parse_line(b"not_a_number", &ParseOptions::default()), None);
+        assert_eq!(parse_line(b"0xinvalid", &ParseOptions::default()), None);
+        assert_eq!(parse_line(b"12.34.56", &ParseOptions::default()), None);
+    }
+
+    #[test]
+    fn test_parse_line_comments() {
+        let opts = ParseOptions::default();
+        assert_eq!(parse_line(b"# header", &opts), None);
+        assert_eq!(parse_line(b"   #42", &opts), None);
+
+        let semicolon = ParseOptions {
+            comment: b";".to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"; note", &semicolon), None);
+        assert_eq!(parse_line(b"42", &semicolon), Some(42.0));
+
+        let slashes = ParseOptions {
+            comment: b"//".to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"// note", &slashes), None);
+        assert_eq!(parse_line(b"/2", &slashes), None);
+    }
+
+    #[test]
+    fn test_parse_chunk_skips_comments() {
+        let chunk = b"# latency in ns\n10\n  # mid-file note\n20\n";
+        let result = parse_chunk(chunk, &ParseOptions::default());
+        assert_eq!(result, vec![10.0, 20.0]);
+    }
+
+    #[test]
+    fn test_parse_line_tolerant_utf8() {
+        let strict = ParseOptions::default();
+        let tolerant = ParseOptions {
+            tolerant_utf8: true,
+            ..Default::default()
+        };
+
+        assert_eq!(parse_line(b"42.5\xff", &strict), None);
+        assert_eq!(parse_line(b"42.5\xff", &tolerant), Some(42.5));
+        assert_eq!(parse_line(b"0x1F\xff", &tolerant), Some(31.0));
+        assert_eq!(parse_line(b"0xff\xe9", &tolerant), Some(255.0));
+        assert_eq!(parse_line(b"\xff42", &tolerant), None);
+    }
+
+    #[test]
+    fn test_read_buffered() {
+        let input: &[u8] = b"10\n\n# note\n0x20\n  30.5  \n";
+        let result = read_buffered(input, &ParseOptions::default());
+        assert_eq!(result, vec![10.0, 32.0, 30.5]);
+    }
+
+    #[test]
+    fn test_read_buffered_tolerant_utf8() {
+        let input: &[u8] = b"10\xff\n20\n";
+        let opts = ParseOptions {
+            tolerant_utf8: true,
+            ..Default::default()
+        };
+        assert_eq!(read_buffered(input, &opts), vec![10.0, 20.0]);
+    }
+
+    #[test]
+    fn test_extract_field() {
+        assert_eq!(extract_field(b"a,b,c", b',', 0), Some(&b"a"[..]));
+        assert_eq!(extract_field(b"a,b,c", b',', 2), Some(&b"c"[..]));
+        assert_eq!(extract_field(b"a,b,c", b',', 3), None);
+        assert_eq!(extract_field(b"a\tb", b'\t', 1), Some(&b"b"[..]));
+        assert_eq!(extract_field(b"a,,c", b',', 1), Some(&b""[..]));
+        assert_eq!(extract_field(b"abc", b',', 0), Some(&b"abc"[..]));
+    }
+
+    #[test]
+    fn test_parse_line_column() {
+        let opts = ParseOptions {
+            field: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"host1,GET,1500", &opts), Some(1500.0));
+        assert_eq!(parse_line(b"host1, GET , 0x10 ", &opts), Some(16.0));
+        assert_eq!(parse_line(b"host1,GET", &opts), None);
+        assert_eq!(parse_line(b"host1,GET,", &opts), None);
+        assert_eq!(parse_line(b"host,method,latency", &opts), None);
+
+        let tsv = ParseOptions {
+            field: Some(1),
+            delimiter: b'\t',
+            scale: 1000.0,
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"a\t2.5\tc", &tsv), Some(2500.0));
+    }
+
+    #[test]
+    fn test_parse_line_decimal_comma() {
+        let opts = ParseOptions {
+            decimal_comma: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"3,14", &opts), "3.14".parse().ok());
+        assert_eq!(parse_line(b"1.000,5", &opts), Some(1000.5));
+        assert_eq!(parse_line(b"1.234.567", &opts), Some(1234567.0));
+        assert_eq!(parse_line(b"-2,5", &opts), Some(-2.5));
+        assert_eq!(parse_line(b" -1.000,25 ", &opts), Some(-1000.25));
+        assert_eq!(parse_line(b"42", &opts), Some(42.0));
+        assert_eq!(parse_line(b"0x10", &opts), Some(16.0));
+        assert_eq!(parse_line(b"1,2,3", &opts), None);
+
+        // Columns are split on the delimiter before the comma is read as a decimal point
+        let semicolon = ParseOptions {
+            decimal_comma: true,
+            field: Some(1),
+            delimiter: b';',
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"host;1.500,75;GET", &semicolon), Some(1500.75));
+
+        let suffixed = ParseOptions {
+            decimal_comma: true,
+            parse_suffix: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_line(b"2,5ms", &suffixed), Some(2.5e6));
+    }
+
+    #[test]
+    fn test_parse_chunk_column() {
+        let chunk = b"name,value\na,1\nb\nc,3\n";
+        let opts = ParseOptions {
+            field: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(parse_chunk(chunk, &opts), vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_header_end() {
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        assert_eq!(header_end(b"name\n1\n2\n", &opts), 5);
+        assert_eq!(header_end(b"\n# note\nname\n1\n", &opts), 13);
+        assert_eq!(header_end(b"name", &opts), 4);
+        assert_eq!(header_end(b"", &opts), 0);
+        assert_eq!(header_end(b"name\n1\n", &ParseOptions::default()), 0);
+
+        let two = ParseOptions {
+            header_lines: 2,
+            ..Default::default()
+        };
+        assert_eq!(header_end(b"a\n\nb\n3\n", &two), 5);
+        assert_eq!(header_end(b"a\n", &two), 2);
+    }
+
+    #[test]
+    fn test_read_buffered_skip_header() {
+        let input: &[u8] = b"\nname,value\nunits,ms\na,1\nb,2\n";
+        let opts = ParseOptions {
+            header_lines: 2,
+            field: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(read_buffered(input, &opts), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_read_file_mmap_skip_header() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        // Long header so it spans several chunk boundaries on multi-threaded runs
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "{}", "7".repeat(10_000)).unwrap();
+        for i in 1..=100 {
+            writeln!(temp_file, "{}", i).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        let result = read_file_mmap(&file, &opts);
+
+        assert_eq!(result, (1..=100).map(|i| i as f64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_read_file_mmap_head() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        // Enough lines for several rounds of blocks, with a comment and an invalid line
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "# comment\nnope").unwrap();
+        for i in 1..=200_000 {
+            writeln!(temp_file, "{}", i).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions::default();
+        for limit in [0, 1, 1000, 150_000] {
+            let result = read_file_mmap_head(&file, &opts, limit);
+            assert_eq!(result, (1..=limit).map(|i| i as f64).collect::<Vec<_>>());
+        }
+        assert_eq!(read_file_mmap_head(&file, &opts, 1_000_000).len(), 200_000);
+    }
+
+    #[test]
+    fn test_read_file_mmap_head_binary() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
 
-    let mmap = unsafe {
-        Mmap::map(file).unwrap_or_else(|e| {
-            eprintln!("error mapping file: {}", e);
-            std::process::exit(1);
-        })
-    };
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=10 {
+            temp_file.write_all(&(i as f64).to_le_bytes()).unwrap();
+        }
+        temp_file.flush().unwrap();
 
-    if mmap.is_empty() {
-        return Vec::new();
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            binary: true,
+            ..Default::default()
+        };
+        assert_eq!(read_file_mmap_head(&file, &opts, 3), vec![1.0, 2.0, 3.0]);
+        assert_eq!(read_file_mmap_head(&file, &opts, 20).len(), 10);
     }
 
-    let num_threads = rayon::current_num_threads();
-    let chunk_size = mmap.len().div_ceil(num_threads);
+    #[test]
+    fn test_read_file_mmap_indexed() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
 
-    // Chunk boundaries must align to line breaks to avoid splitting numbers mid-parse
-    let mut boundaries = vec![0];
-    for i in 1..num_threads {
-        let mut pos = i * chunk_size;
-        if pos >= mmap.len() {
-            break;
-        }
-        while pos < mmap.len() && mmap[pos] != b'\n' {
-            pos += 1;
-        }
-        if pos < mmap.len() {
-            boundaries.push(pos + 1); // Start after the newline
+        // A header, comments, blank and invalid lines all take up line numbers
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "value\n# comment\n\nnope").unwrap();
+        for i in 1..=100_000 {
+            writeln!(temp_file, "{}", i).unwrap();
         }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        let result = read_file_mmap_indexed(&file, &opts);
+        let expected: Vec<(f64, usize)> = (1..=100_000).map(|i| (i as f64, i + 4)).collect();
+        assert_eq!(result, expected);
     }
-    boundaries.push(mmap.len());
 
-    let chunks: Vec<_> = boundaries.windows(2).map(|w| (w[0], w[1])).collect();
+    #[test]
+    fn test_indexed_values() {
+        let opts = ParseOptions {
+            header_lines: 1,
+            uniq_c: true,
+            ..Default::default()
+        };
+        let input: &[u8] = b"count value\n\n2 5\n# skip\n1 7\n";
+        let result: Vec<_> = indexed_values(input, &opts).collect();
+        assert_eq!(result, vec![(5.0, 3), (5.0, 3), (7.0, 5)]);
+    }
 
-    let results: Vec<Vec<f64>> = chunks
-        .par_iter()
-        .map(|&(start, end)| {
-            let chunk = &mmap[start..end];
-            parse_chunk(chunk, scale)
-        })
-        .collect();
+    #[test]
+    fn test_read_file_mmap_token_split_single_line() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
 
-    results.into_iter().flatten().collect()
-}
+        // One line of space- and tab-separated hex, with no trailing newline
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let tokens: Vec<String> = (0..5000).map(|i| format!("0x{:x}", i * 4096)).collect();
+        write!(temp_file, "{}", tokens.join(" \t")).unwrap();
+        temp_file.flush().unwrap();
 
-/// Parses newline-delimited numbers from byte slice.
-/// Returns values scaled to base units (ignores invalid lines silently).
-fn parse_chunk(chunk: &[u8], scale: f64) -> Vec<f64> {
-    let mut values = Vec::new();
-    let mut start = 0;
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            token_split: true,
+            ..Default::default()
+        };
+        let expected: Vec<f64> = (0..5000).map(|i| (i * 4096) as f64).collect();
 
-    for (i, &byte) in chunk.iter().enumerate() {
-        if byte == b'\n' {
-            if i > start {
-                let line = &chunk[start..i];
-                if let Some(value) = parse_line(line, scale) {
-                    values.push(value);
-                }
-            }
-            start = i + 1;
-        }
-    }
+        // Enough threads that chunk boundaries have to land mid-line
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            assert_eq!(line_chunks(&file_bytes(&file), &opts).len(), 8);
+            assert_eq!(read_file_mmap(&file, &opts), expected);
+            assert_eq!(read_file_mmap_head(&file, &opts, 1234), expected[..1234]);
+        });
 
-    // Handle last line if no trailing newline
-    if start < chunk.len() {
-        let line = &chunk[start..];
-        if let Some(value) = parse_line(line, scale) {
-            values.push(value);
-        }
+        // Without token splitting the line is one unparseable value
+        assert!(read_file_mmap(&file, &ParseOptions::default()).is_empty());
     }
 
-    values
-}
+    #[test]
+    fn test_read_file_mmap_token_split_filter_keeps_lines_whole() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
 
-/// Parses a single line as either decimal float or hex (0x prefix).
-/// Returns None for invalid input rather than panicking (for robustness with untrusted input).
-fn parse_line(line: &[u8], scale: f64) -> Option<f64> {
-    let mut start = 0;
-    let mut end = line.len();
+        // Two long lines; only the first starts with what the filter looks for
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let line = |start: usize| -> String {
+            let tokens: Vec<String> = (start..start + 5000).map(|i| format!("{}", i)).collect();
+            tokens.join(" ")
+        };
+        writeln!(temp_file, "{}\n{}", line(0), line(5000)).unwrap();
+        temp_file.flush().unwrap();
 
-    while start < end && line[start].is_ascii_whitespace() {
-        start += 1;
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            token_split: true,
+            filter: Some(Regex::new("^0 ").unwrap()),
+            ..Default::default()
+        };
+        let expected: Vec<f64> = (0..5000).map(f64::from).collect();
+
+        // Were chunks cut mid-line, the filter would only see the first piece of each line
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            assert_eq!(read_file_mmap(&file, &opts), expected);
+        });
     }
-    while end > start && line[end - 1].is_ascii_whitespace() {
-        end -= 1;
+
+    fn file_bytes(file: &File) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        file.try_clone().unwrap().read_to_end(&mut bytes).unwrap();
+        bytes
     }
 
-    if start == end {
-        return None;
+    #[test]
+    fn test_parse_chunk_token_split() {
+        let opts = ParseOptions {
+            token_split: true,
+            ..Default::default()
+        };
+        let chunk = b"1 2\t3\r\n# 4 5\n\n  6  nope 7\n";
+        assert_eq!(parse_chunk(chunk, &opts), vec![1.0, 2.0, 3.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn test_indexed_values_token_split() {
+        let opts = ParseOptions {
+            token_split: true,
+            header_lines: 1,
+            ..Default::default()
+        };
+        let input: &[u8] = b"a b\n1 2\n# 9\n3\n";
+        let result: Vec<_> = indexed_values(input, &opts).collect();
+        assert_eq!(result, vec![(1.0, 2), (2.0, 2), (3.0, 4)]);
     }
 
-    let trimmed = &line[start..end];
+    #[test]
+    fn test_total_file_mmap() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
 
-    let s = std::str::from_utf8(trimmed).ok()?;
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "n\nnan").unwrap();
+        for i in 1..=100_000 {
+            writeln!(temp_file, "{}", i).unwrap();
+        }
+        temp_file.flush().unwrap();
 
-    if let Some(hex) = s.strip_prefix("0x") {
-        u64::from_str_radix(hex, 16)
-            .ok()
-            .map(|v| (v as f64) * scale)
-    } else {
-        s.parse::<f64>().ok().map(|v| v * scale)
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        let totals = total_file_mmap(&file, &opts);
+        assert_eq!(totals.count, 100_000);
+        assert_eq!(totals.non_finite, 1);
+        assert_eq!(totals.sum(), 5_000_050_000.0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::units::Unit;
+    #[test]
+    fn test_total_file_mmap_binary() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for x in [1.5, 2.5, 4.0] {
+            temp_file.write_all(&f64::to_le_bytes(x)).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            binary: true,
+            scale: 2.0,
+            ..Default::default()
+        };
+        let totals = total_file_mmap(&file, &opts);
+        assert_eq!((totals.count, totals.sum()), (3, 16.0));
+    }
 
     #[test]
-    fn test_parse_line_decimal() {
-        assert_eq!(parse_line(b"42.5", 1.0), Some(42.5));
-        assert_eq!(parse_line(b"100", 1.0), Some(100.0));
-        assert_eq!(parse_line(b"0", 1.0), Some(0.0));
-        assert_eq!(parse_line(b"-5.5", 1.0), Some(-5.5));
+    fn test_parse_chunk_filter() {
+        let mut opts = ParseOptions {
+            filter: Some(Regex::new(r"^latency=").unwrap()),
+            field: Some(1),
+            delimiter: b'=',
+            ..Default::default()
+        };
+        let chunk = b"latency=12\nsize=4096\nlatency=30\r\nstartup done\n";
+        assert_eq!(parse_chunk(chunk, &opts), vec![12.0, 30.0]);
+
+        opts.invert_filter = true;
+        assert_eq!(parse_chunk(chunk, &opts), vec![4096.0]);
     }
 
     #[test]
-    fn test_parse_line_hex() {
-        assert_eq!(parse_line(b"0x10", 1.0), Some(16.0));
-        assert_eq!(parse_line(b"0xFF", 1.0), Some(255.0));
-        assert_eq!(parse_line(b"0x0", 1.0), Some(0.0));
-        assert_eq!(parse_line(b"0xDEADBEEF", 1.0), Some(3735928559.0));
+    fn test_read_buffered_filter_skips_unparseable_lines() {
+        // Lines the filter drops aren't parse errors, even on the strict streaming path
+        let opts = ParseOptions {
+            filter: Some(Regex::new(r"^\d").unwrap()),
+            header_lines: 1,
+            ..Default::default()
+        };
+        let input: &[u8] = b"1 header\nINFO starting\n5\nWARN slow\n7\n";
+        assert_eq!(read_buffered(input, &opts), vec![5.0, 7.0]);
     }
 
     #[test]
-    fn test_parse_line_with_whitespace() {
-        assert_eq!(parse_line(b"  42.5  ", 1.0), Some(42.5));
-        assert_eq!(parse_line(b"\t100\n", 1.0), Some(100.0));
-        assert_eq!(parse_line(b"  0x10  ", 1.0), Some(16.0));
+    fn test_read_buffered_skips_lines_without_field() {
+        // Short lines and empty fields are skipped, as on the mmap path
+        let opts = ParseOptions {
+            field: Some(1),
+            ..Default::default()
+        };
+        let input: &[u8] = b"a,1\nb\nc,\nd,3\n";
+        assert_eq!(read_buffered(input, &opts), vec![1.0, 3.0]);
+
+        let opts = ParseOptions {
+            uniq_c: true,
+            ..opts
+        };
+        assert_eq!(read_buffered(&b"2 a,1\n1 b\n"[..], &opts), vec![1.0, 1.0]);
     }
 
     #[test]
-    fn test_parse_line_with_scale() {
-        assert_eq!(parse_line(b"10", 2.0), Some(20.0));
-        assert_eq!(parse_line(b"5.5", 1000.0), Some(5500.0));
-        assert_eq!(parse_line(b"0x10", 10.0), Some(160.0));
+    fn test_read_buffered_skipping_invalid() {
+        let opts = ParseOptions {
+            field: Some(1),
+            ..Default::default()
+        }
+        .skipping_invalid();
+        let input: &[u8] = b"a,1\nb,nope\nc\nd,3\n";
+        assert_eq!(read_buffered(input, &opts), vec![1.0, 3.0]);
     }
 
     #[test]
-    fn test_parse_line_invalid() {
-        assert_eq!(parse_line(b"", 1.0), None);
-        assert_eq!(parse_line(b"   ", 1.0), None);
-        assert_eq!(parse_line(b"not_a_number", 1.0), None);
-        assert_eq!(parse_line(b"0xinvalid", 1.0), None);
-        assert_eq!(parse_line(b"12.34.56", 1.0), None);
+    fn test_parse_entry_uniq_c() {
+        let opts = ParseOptions {
+            uniq_c: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_entry(b"   42 1500", &opts), Some((1500.0, 42)));
+        assert_eq!(parse_entry(b"1\t0x10", &opts), Some((16.0, 1)));
+        assert_eq!(parse_entry(b"0 7", &opts), Some((7.0, 0)));
+        assert_eq!(parse_entry(b"1500", &opts), None);
+        assert_eq!(parse_entry(b"-1 5", &opts), None);
+        assert_eq!(parse_entry(b"2.5 5", &opts), None);
+
+        let plain = ParseOptions::default();
+        assert_eq!(parse_entry(b"1500", &plain), Some((1500.0, 1)));
+    }
+
+    #[test]
+    fn test_parse_chunk_uniq_c() {
+        let opts = ParseOptions {
+            uniq_c: true,
+            ..Default::default()
+        };
+        let chunk = b"   3 10\n   1 20";
+        assert_eq!(parse_chunk(chunk, &opts), vec![10.0, 10.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn test_read_buffered_uniq_c() {
+        let input: &[u8] = b"   3 10\n   1 20\n";
+        let opts = ParseOptions {
+            uniq_c: true,
+            scale: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(read_buffered(input, &opts), vec![20.0, 20.0, 20.0, 40.0]);
     }
 
     #[test]
     fn test_parse_chunk_single_line() {
         let chunk = b"42.5\n";
-        let result = parse_chunk(chunk, 1.0);
+        let result = parse_chunk(chunk, &ParseOptions::default());
         assert_eq!(result, vec![42.5]);
     }
 
     #[test]
     fn test_parse_chunk_multiple_lines() {
         let chunk = b"10\n20\n30\n";
-        let result = parse_chunk(chunk, 1.0);
+        let result = parse_chunk(chunk, &ParseOptions::default());
         assert_eq!(result, vec![10.0, 20.0, 30.0]);
     }
 
     #[test]
     fn test_parse_chunk_mixed_formats() {
         let chunk = b"10\n0x20\n30.5\n";
-        let result = parse_chunk(chunk, 1.0);
+        let result = parse_chunk(chunk, &ParseOptions::default());
         assert_eq!(result, vec![10.0, 32.0, 30.5]);
     }
 
     #[test]
     fn test_parse_chunk_with_invalid_lines() {
         let chunk = b"10\ninvalid\n20\n";
-        let result = parse_chunk(chunk, 1.0);
+        let result = parse_chunk(chunk, &ParseOptions::default());
         assert_eq!(result, vec![10.0, 20.0]); // Invalid line is skipped
     }
 
     #[test]
     fn test_parse_chunk_no_trailing_newline() {
         let chunk = b"10\n20\n30";
-        let result = parse_chunk(chunk, 1.0);
+        let result = parse_chunk(chunk, &ParseOptions::default());
         assert_eq!(result, vec![10.0, 20.0, 30.0]);
     }
 
     #[test]
     fn test_parse_chunk_empty_lines() {
         let chunk = b"10\n\n20\n\n\n30\n";
-        let result = parse_chunk(chunk, 1.0);
+        let result = parse_chunk(chunk, &ParseOptions::default());
         assert_eq!(result, vec![10.0, 20.0, 30.0]);
     }
 
+    #[test]
+    fn test_parse_chunk_crlf() {
+        let chunk = b"10\r\n0x10\r\n# note\r\n\r\n2.5e1\r\n-3\r\n";
+        let result = parse_chunk(chunk, &ParseOptions::default());
+        assert_eq!(result, vec![10.0, 16.0, 25.0, -3.0]);
+
+        let csv = ParseOptions {
+            field: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_chunk(b"a,1\r\nb,0x2\r\nc,3", &csv),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert_eq!(
+            read_buffered(&b"1\r\n0x2\r\n"[..], &ParseOptions::default()),
+            vec![1.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn test_strip_cr() {
+        assert_eq!(strip_cr(b"0x10\r"), b"0x10");
+        assert_eq!(strip_cr(b"0x10"), b"0x10");
+        assert_eq!(strip_cr(b"\r"), b"");
+        assert_eq!(strip_cr(b"1\r\r"), b"1\r");
+    }
+
+    #[test]
+    fn test_tail_holds_back_partial_lines() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        let mut tail = Tail::new(temp_file.reopen().unwrap(), &opts);
+        assert_eq!(tail.read_appended(&opts).unwrap(), Vec::<f64>::new());
+
+        write!(temp_file, "latency\n1\n2\n3").unwrap();
+        temp_file.flush().unwrap();
+        assert_eq!(tail.read_appended(&opts).unwrap(), vec![1.0, 2.0]);
+
+        // The partial "3" completes as "35" once the rest of the line arrives
+        write!(temp_file, "5\r\n# comment\n4\n").unwrap();
+        temp_file.flush().unwrap();
+        assert_eq!(tail.read_appended(&opts).unwrap(), vec![35.0, 4.0]);
+        assert_eq!(tail.read_appended(&opts).unwrap(), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn test_read_file_mmap_with_progress_counts_every_byte() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        // Several progress blocks, plus a header that's counted without being parsed
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "value").unwrap();
+        for i in 1..=300_000 {
+            writeln!(temp_file, "{}", i).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        let progress = AtomicUsize::new(0);
+        let result = read_file_mmap_with_progress(&file, &opts, &progress);
+
+        assert_eq!(result, (1..=300_000).map(|i| i as f64).collect::<Vec<_>>());
+        let len = file.metadata().unwrap().len() as usize;
+        assert_eq!(progress.load(Ordering::Relaxed), len);
+    }
+
     #[test]
     fn test_parse_chunk_with_scale() {
         let chunk = b"1\n2\n3\n";
-        let result = parse_chunk(chunk, 1000.0);
+        let result = parse_chunk(chunk, &scaled(1000.0));
         assert_eq!(result, vec![1000.0, 2000.0, 3000.0]);
     }
 
+    #[test]
+    fn test_sample_file_mmap() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "value").unwrap();
+        for i in 1..=10_000 {
+            writeln!(temp_file, "{}", i).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            header_lines: 1,
+            ..Default::default()
+        };
+        let reservoir = sample_file_mmap(&file, &opts, 1000, 5);
+        assert_eq!(reservoir.seen(), 10_000);
+
+        let values = reservoir.into_values();
+        assert_eq!(values.len(), 1000);
+        assert!(values.iter().all(|&x| (1.0..=10_000.0).contains(&x)));
+        let low = values.iter().filter(|&&x| x <= 5000.0).count();
+        assert!((400..600).contains(&low), "{low} of 1000 in the lower half");
+
+        // The per-chunk RNGs are derived from the seed, so the sample is reproducible
+        let again = sample_file_mmap(&file, &opts, 1000, 5).into_values();
+        assert_eq!(values, again);
+    }
+
     #[test]
     fn test_read_file_mmap_with_units() {
         use std::io::Write;
@@ -216,20 +1780,273 @@ mod tests {
         temp_file.flush().unwrap();
 
         let file = temp_file.reopen().unwrap();
-        let result = read_file_mmap(&file, Some(Unit::Microseconds));
+        let result = read_file_mmap(&file, &ParseOptions::new(Some(Unit::Microseconds)));
 
         // Microseconds scale is 1e3, so values should be multiplied
         assert_eq!(result, vec![1_000_000.0, 2_000_000.0, 3_000_000.0]);
     }
 
+    #[test]
+    fn test_read_file_mmap_with_custom_scale() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "1").unwrap();
+        writeln!(temp_file, "0x2").unwrap();
+        writeln!(temp_file, "2.5").unwrap();
+        temp_file.flush().unwrap();
+
+        // Blocks of 4096 bytes
+        let file = temp_file.reopen().unwrap();
+        let result = read_file_mmap(&file, &scaled(4096.0));
+
+        assert_eq!(result, vec![4096.0, 8192.0, 10240.0]);
+    }
+
+    #[test]
+    fn test_parse_line_with_suffix() {
+        let opts = ParseOptions {
+            parse_suffix: true,
+            ..ParseOptions::new(Some(Unit::Milliseconds))
+        };
+
+        assert_eq!(parse_line(b"12ms", &opts), Some(12e6));
+        assert_eq!(parse_line(b"12 ms", &opts), Some(12e6));
+        assert_eq!(parse_line(b"3us", &opts), Some(3e3));
+        assert_eq!(parse_line("3µs".as_bytes(), &opts), Some(3e3));
+        assert_eq!(parse_line(b"1.5s", &opts), Some(1.5e9));
+        assert_eq!(parse_line(b"2e3ns", &opts), Some(2e3));
+        assert_eq!(parse_line(b"4.5MiB", &opts), Some(4.5 * 1024.0 * 1024.0));
+
+        // Unsuffixed lines still use --unit
+        assert_eq!(parse_line(b"7", &opts), Some(7e6));
+        assert_eq!(parse_line(b"1e3", &opts), Some(1e9));
+        assert_eq!(parse_line(b"12parsecs", &opts), None);
+
+        // Off by default
+        assert_eq!(parse_line(b"12ms", &ParseOptions::default()), None);
+    }
+
+    #[test]
+    fn test_parse_line_with_suffix_keeps_radix_integers() {
+        let opts = ParseOptions {
+            parse_suffix: true,
+            ..ParseOptions::default()
+        };
+
+        // A trailing B is a hex digit here, not bytes
+        assert_eq!(parse_line(b"0x1B", &opts), Some(27.0));
+        assert_eq!(parse_line(b"0xAB", &opts), Some(171.0));
+        assert_eq!(parse_line(b"0b11", &opts), Some(3.0));
+        assert_eq!(parse_line(b"0x1.8p3", &opts), Some(12.0));
+        assert_eq!(parse_line(b"1B", &opts), Some(1.0));
+    }
+
+    #[test]
+    fn test_parse_line_with_suffix_and_custom_scale() {
+        let opts = ParseOptions {
+            parse_suffix: true,
+            custom_scale: 2.0,
+            ..scaled(2e6)
+        };
+
+        assert_eq!(parse_line(b"3ms", &opts), Some(6e6));
+        assert_eq!(parse_line(b"1KiB", &opts), Some(2048.0));
+        assert_eq!(parse_line(b"3", &opts), Some(6e6));
+    }
+
+    #[test]
+    fn test_read_file_mmap_mixed_suffixes() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "500ns").unwrap();
+        writeln!(temp_file, "12µs").unwrap();
+        writeln!(temp_file, "12us").unwrap();
+        writeln!(temp_file, "3ms").unwrap();
+        writeln!(temp_file, "2").unwrap();
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            parse_suffix: true,
+            ..ParseOptions::new(Some(Unit::Seconds))
+        };
+        let result = read_file_mmap(&file, &opts);
+
+        assert_eq!(result, vec![500.0, 12e3, 12e3, 3e6, 2e9]);
+    }
+
+    #[test]
+    fn test_try_read_file_mmap() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "1\n2").unwrap();
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let result = try_read_file_mmap(&file, &ParseOptions::default()).unwrap();
+        assert_eq!(result, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_try_read_file_mmap_error() {
+        // Directories can't be mapped
+        let dir = tempfile::tempdir().unwrap();
+        let file = File::open(dir.path()).unwrap();
+
+        let err = try_read_file_mmap(&file, &ParseOptions::default()).unwrap_err();
+        assert!(matches!(err, ReadError::Map(_)));
+        assert!(err.to_string().starts_with("error mapping file"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_file_mmap_fifo() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("values");
+        let made = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(made.success());
+
+        // Opening either end of a FIFO blocks until the other end is opened
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            std::fs::write(writer_path, b"1\n2\n3\n").unwrap();
+        });
+        let file = File::open(&path).unwrap();
+        let result = try_read_file_mmap(&file, &ParseOptions::default()).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(result, vec![1.0, 2.0, 3.0]);
+    }
+
+    fn binary_opts(scale: f64) -> ParseOptions {
+        ParseOptions {
+            binary: true,
+            ..scaled(scale)
+        }
+    }
+
+    #[test]
+    fn test_read_file_mmap_binary_round_trip() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let values = [1.5f64, -2.0, 1e300, 0.0];
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for v in values {
+            temp_file.write_all(&v.to_le_bytes()).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        assert_eq!(read_file_mmap(&file, &binary_opts(1.0)), values);
+        assert_eq!(
+            read_file_mmap(&file, &binary_opts(1000.0)),
+            [1500.0, -2000.0, 1e303, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_decode_binary_unaligned() {
+        let mut bytes = vec![0u8];
+        bytes.extend_from_slice(&42.0f64.to_le_bytes());
+        assert_eq!(
+            decode_binary(&bytes[1..], &binary_opts(1.0)).unwrap(),
+            [42.0]
+        );
+    }
+
+    #[test]
+    fn test_decode_binary_partial_value() {
+        let mut bytes = 1.0f64.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0, 0, 0]);
+
+        let err = decode_binary(&bytes, &binary_opts(1.0)).unwrap_err();
+        assert!(matches!(err, ReadError::PartialValue { trailing: 3 }));
+    }
+
+    #[test]
+    fn test_read_binary_stream() {
+        let bytes: Vec<u8> = [3.0f64, 4.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        assert_eq!(read_binary(&bytes[..], &binary_opts(2.0)), [6.0, 8.0]);
+    }
+
+    fn json_opts(path: &str) -> ParseOptions {
+        ParseOptions {
+            json_path: Some(path.split('.').map(String::from).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_line_json_field() {
+        let opts = json_opts("latency_ns");
+        assert_eq!(
+            parse_line(br#"{"latency_ns": 1234, "path": "/"}"#, &opts),
+            Some(1234.0)
+        );
+        assert_eq!(parse_line(br#"{"latency_ns": 1.5e3}"#, &opts), Some(1500.0));
+        assert_eq!(parse_line(br#"{"other": 1}"#, &opts), None);
+        assert_eq!(parse_line(br#"{"latency_ns": "12"}"#, &opts), None);
+        assert_eq!(parse_line(b"[1, 2]", &opts), None);
+        assert_eq!(parse_line(b"1234", &opts), None);
+        assert_eq!(parse_line(b"# {\"latency_ns\": 1}", &opts), None);
+    }
+
+    #[test]
+    fn test_parse_line_json_nested() {
+        let opts = json_opts("timing.server.total");
+        let line = br#"{"timing": {"server": {"total": 42}}, "timing.server.total": 1}"#;
+        assert_eq!(parse_line(line, &opts), Some(42.0));
+        assert_eq!(parse_line(br#"{"timing": {"server": 5}}"#, &opts), None);
+    }
+
+    #[test]
+    fn test_read_file_mmap_json_lines() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, r#"{{"req": {{"ms": 3}}}}"#).unwrap();
+        writeln!(temp_file, "not json").unwrap();
+        writeln!(temp_file, r#"{{"req": {{"ms": 4.5}}}}"#).unwrap();
+        temp_file.flush().unwrap();
+
+        let file = temp_file.reopen().unwrap();
+        let opts = ParseOptions {
+            scale: 1e6,
+            ..json_opts("req.ms")
+        };
+        assert_eq!(read_file_mmap(&file, &opts), vec![3e6, 4.5e6]);
+    }
+
+    #[test]
+    fn test_read_buffered_json_skips_lines_without_key() {
+        let opts = json_opts("a");
+        let input: &[u8] = b"{\"a\": 1}\n{\"b\": 2}\n[1, 2]\nnot json\n{\"a\": 3}\n";
+        assert_eq!(read_buffered(input, &opts), vec![1.0, 3.0]);
+
+        let opts = json_opts("req.ms");
+        let input: &[u8] = b"{\"req\": 5}\n{\"req\": {\"ms\": 4}}\n";
+        assert_eq!(read_buffered(input, &opts), vec![4.0]);
+    }
+
     #[test]
     fn test_read_file_mmap_empty() {
         use tempfile::NamedTempFile;
 
         let temp_file = NamedTempFile::new().unwrap();
         let file = temp_file.reopen().unwrap();
-        let result = read_file_mmap(&file, None);
+        let result = read_file_mmap(&file, &ParseOptions::default());
 
-        assert_eq!(result, vec![]);
+        assert_eq!(result, Vec::<f64>::new());
     }
 }
@@ -0,0 +1,194 @@
+/// Keeps a uniformly random `n` of the values (all of them if there are no more than `n`),
+/// via a partial Fisher-Yates shuffle. The kept values come out in random order.
+pub fn subsample(mut data: Vec<f64>, n: usize, rng: &mut fastrand::Rng) -> Vec<f64> {
+    if data.len() <= n {
+        return data;
+    }
+
+    for i in 0..n {
+        let j = rng.usize(i..data.len());
+        data.swap(i, j);
+    }
+    data.truncate(n);
+    data
+}
+
+/// A uniform random sample of at most `capacity` values from a stream of unknown length
+/// (Vitter's Algorithm R). Reservoirs filled from disjoint parts of the input, such as the
+/// per-thread chunks of an mmapped file, can be merged into a sample of the whole input.
+pub struct Reservoir {
+    capacity: usize,
+    seen: usize,
+    values: Vec<f64>,
+}
+
+impl Reservoir {
+    pub fn new(capacity: usize) -> Self {
+        Reservoir {
+            capacity,
+            seen: 0,
+            values: Vec::new(),
+        }
+    }
+
+    /// Offers a value to the sample: the i-th value seen replaces a random kept value
+    /// with probability capacity / i, which keeps every value equally likely to be kept
+    pub fn push(&mut self, value: f64, rng: &mut fastrand::Rng) {
+        self.seen += 1;
+        if self.values.len() < self.capacity {
+            self.values.push(value);
+        } else {
+            let j = rng.usize(..self.seen);
+            if j < self.capacity {
+                self.values[j] = value;
+            }
+        }
+    }
+
+    pub fn extend(&mut self, values: impl IntoIterator<Item = f64>, rng: &mut fastrand::Rng) {
+        for value in values {
+            self.push(value, rng);
+        }
+    }
+
+    /// Combines samples of two disjoint streams into a sample of both. Each kept slot is
+    /// drawn from one side with probability proportional to how many of that side's values
+    /// haven't been drawn yet, so the split follows the same hypergeometric distribution as
+    /// sampling the concatenated streams directly.
+    pub fn merge(self, other: Reservoir, rng: &mut fastrand::Rng) -> Reservoir {
+        let capacity = self.capacity.min(other.capacity);
+        let mut left = subsample(self.values, capacity, rng);
+        let mut right = subsample(other.values, capacity, rng);
+        rng.shuffle(&mut left);
+        rng.shuffle(&mut right);
+
+        let (mut left_remaining, mut right_remaining) = (self.seen, other.seen);
+        let mut values = Vec::with_capacity(capacity.min(self.seen + other.seen));
+        while values.len() < capacity && left_remaining + right_remaining > 0 {
+            if rng.usize(..left_remaining + right_remaining) < left_remaining {
+                values.push(left.pop().unwrap());
+                left_remaining -= 1;
+            } else {
+                values.push(right.pop().unwrap());
+                right_remaining -= 1;
+            }
+        }
+
+        Reservoir {
+            capacity,
+            seen: self.seen + other.seen,
+            values,
+        }
+    }
+
+    /// How many values have been offered, kept or not
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    pub fn into_values(self) -> Vec<f64> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::sort_floats;
+
+    #[test]
+    fn test_subsample_keeps_n_distinct_values() {
+        let data: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        let mut sample = subsample(data, 100, &mut fastrand::Rng::with_seed(1));
+        assert_eq!(sample.len(), 100);
+
+        sort_floats(&mut sample);
+        sample.dedup();
+        assert_eq!(sample.len(), 100);
+        assert!(sample.iter().all(|&x| (0.0..1000.0).contains(&x)));
+    }
+
+    #[test]
+    fn test_subsample_small_input_unchanged() {
+        let data = vec![3.0, 1.0, 2.0];
+        let sample = subsample(data.clone(), 10, &mut fastrand::Rng::with_seed(1));
+        assert_eq!(sample, data);
+    }
+
+    #[test]
+    fn test_subsample_reproducible_with_seed() {
+        let data: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        let a = subsample(data.clone(), 50, &mut fastrand::Rng::with_seed(42));
+        let b = subsample(data, 50, &mut fastrand::Rng::with_seed(42));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_reservoir_keeps_everything_under_capacity() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        let mut reservoir = Reservoir::new(10);
+        reservoir.extend([1.0, 2.0, 3.0], &mut rng);
+        assert_eq!(reservoir.seen(), 3);
+        assert_eq!(reservoir.into_values(), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_reservoir_is_roughly_uniform() {
+        let mut rng = fastrand::Rng::with_seed(5);
+        let mut reservoir = Reservoir::new(2000);
+        reservoir.extend((0..10_000).map(|i| i as f64), &mut rng);
+        assert_eq!(reservoir.seen(), 10_000);
+
+        let values = reservoir.into_values();
+        assert_eq!(values.len(), 2000);
+        let low = values.iter().filter(|&&x| x < 5000.0).count();
+        assert!(
+            (900..1100).contains(&low),
+            "{low} of 2000 in the lower half"
+        );
+    }
+
+    #[test]
+    fn test_reservoir_merge_weights_by_seen() {
+        // 9000 values on the left and 1000 on the right: a fair merged sample takes ~90% left
+        let mut rng = fastrand::Rng::with_seed(9);
+        let mut left = Reservoir::new(1000);
+        left.extend(std::iter::repeat_n(0.0, 9000), &mut rng);
+        let mut right = Reservoir::new(1000);
+        right.extend(std::iter::repeat_n(1.0, 1000), &mut rng);
+
+        let merged = left.merge(right, &mut rng);
+        assert_eq!(merged.seen(), 10_000);
+        let values = merged.into_values();
+        assert_eq!(values.len(), 1000);
+        let from_right = values.iter().filter(|&&x| x == 1.0).count();
+        assert!(
+            (60..140).contains(&from_right),
+            "{from_right} of 1000 from the right"
+        );
+    }
+
+    #[test]
+    fn test_reservoir_merge_small_sides() {
+        let mut rng = fastrand::Rng::with_seed(11);
+        let mut left = Reservoir::new(10);
+        left.extend([1.0, 2.0], &mut rng);
+        let mut right = Reservoir::new(10);
+        right.extend([3.0], &mut rng);
+
+        let mut values = left.merge(right, &mut rng).into_values();
+        sort_floats(&mut values);
+        assert_eq!(values, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_subsample_is_roughly_uniform() {
+        let data: Vec<f64> = (0..10_000).map(|i| i as f64).collect();
+        let sample = subsample(data, 2000, &mut fastrand::Rng::with_seed(7));
+        let low = sample.iter().filter(|&&x| x < 5000.0).count();
+        assert!(
+            (900..1100).contains(&low),
+            "{low} of 2000 in the lower half"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use rayon::prelude::*;
+
+/// Sorts floats in ascending order, in parallel. Uses `f64::total_cmp`, so unlike
+/// `partial_cmp(..).unwrap()` it can't panic on NaN: NaNs sort to the ends (by sign bit) and
+/// -0.0 comes before 0.0.
+pub fn sort_floats(data: &mut [f64]) {
+    data.par_sort_unstable_by(f64::total_cmp);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_floats() {
+        let mut data = vec![3.0, -1.0, 2.5, 0.0, -7.0];
+        sort_floats(&mut data);
+        assert_eq!(data, vec![-7.0, -1.0, 0.0, 2.5, 3.0]);
+    }
+
+    #[test]
+    fn test_sort_floats_with_nan() {
+        let mut data = vec![
+            2.0,
+            f64::NAN,
+            f64::INFINITY,
+            -f64::NAN,
+            -1.0,
+            f64::NEG_INFINITY,
+            0.0,
+            -0.0,
+        ];
+        sort_floats(&mut data);
+
+        assert!(data[0].is_nan() && data[0].is_sign_negative());
+        assert_eq!(
+            &data[1..7],
+            [f64::NEG_INFINITY, -1.0, -0.0, 0.0, 2.0, f64::INFINITY]
+        );
+        assert!(data[3].is_sign_negative() && data[4].is_sign_positive());
+        assert!(data[7].is_nan() && data[7].is_sign_positive());
+    }
+}
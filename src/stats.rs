@@ -1,64 +1,911 @@
+use crate::sorting::sort_floats;
+use rayon::prelude::*;
+
 /// Pre-computed statistics over sorted dataset.
 /// Data is kept sorted to enable efficient quantile lookups & binary search.
+/// Non-finite values (NaN, ±infinity) are dropped up front and only counted.
+/// Empty input is allowed: every statistic is then NaN (and `n` is 0) rather than a panic.
 pub struct Stats {
     pub data: Vec<f64>,
     pub n: usize,
+    pub non_finite: usize,
     pub sum: f64,
     pub mean: f64,
     pub geo_mean: f64,
+    pub geo_std_dev: f64,
+    pub harmonic_mean: f64,
     pub variance: f64,
     pub std_dev: f64,
+    /// How `quantile` picks a value between ranks (linear unless set otherwise)
+    pub quantile_method: QuantileMethod,
+    /// Whether the geometric statistics skip non-positive values instead of being NaN
+    pub positive_geometric: bool,
 }
 
 impl Stats {
-    pub fn new(mut data: Vec<f64>) -> Self {
-        data.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    pub fn new(data: Vec<f64>) -> Self {
+        Self::build(data, false)
+    }
+
+    /// Like `new`, but for data that's already in ascending order, skipping the sort.
+    /// Debug builds check the order; release builds trust it, and unsorted data then gives
+    /// wrong quantiles.
+    pub fn from_sorted(data: Vec<f64>) -> Self {
+        Self::build(data, true)
+    }
+
+    fn build(mut data: Vec<f64>, sorted: bool) -> Self {
+        let total = data.len();
+        data.retain(|x| x.is_finite());
+        let non_finite = total - data.len();
+
+        if sorted {
+            debug_assert!(
+                data.is_sorted(),
+                "data passed to Stats::from_sorted isn't sorted"
+            );
+        } else {
+            sort_floats(&mut data);
+        }
 
         let n = data.len();
-        let sum: f64 = data.iter().sum();
+        let moments = data
+            .par_iter()
+            .fold(Moments::default, |mut acc, &x| {
+                acc.push(x);
+                acc
+            })
+            .reduce(Moments::default, Moments::merge);
+
+        let sum = moments.sum.total();
         let mean = sum / n as f64;
+        let variance = moments.variance();
+
+        let all_positive = moments.all_positive;
+
+        let geo_mean = if all_positive {
+            (moments.log_sum.total() / n as f64).exp()
+        } else {
+            f64::NAN
+        };
 
-        let geo_mean = if data.iter().all(|&x| x > 0.0) {
-            let log_sum: f64 = data.iter().map(|x| x.ln()).sum();
-            (log_sum / n as f64).exp()
+        // exp of the logs' standard deviation: a multiplicative spread factor, so values
+        // within one geometric std dev of gmean lie in [gmean / gsd, gmean × gsd]
+        let geo_std_dev = if all_positive {
+            moments.logs.variance().sqrt().exp()
+        } else {
+            f64::NAN
+        };
+
+        let harmonic_mean = if all_positive {
+            n as f64 / moments.reciprocal_sum.total()
         } else {
             f64::NAN
         };
 
-        let variance = data.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
         let std_dev = variance.sqrt();
 
         Stats {
             data,
             n,
+            non_finite,
             sum,
             mean,
             geo_mean,
+            geo_std_dev,
+            harmonic_mean,
             variance,
             std_dev,
+            quantile_method: QuantileMethod::Linear,
+            positive_geometric: false,
+        }
+    }
+
+    pub fn with_quantile_method(mut self, method: QuantileMethod) -> Self {
+        self.quantile_method = method;
+        self
+    }
+
+    /// When `on`, computes the geometric mean and std dev over just the positive values
+    /// rather than leaving them NaN if any value is zero or negative. Those values have no
+    /// logarithm, so this summarizes a different population: dropping them biases gmean
+    /// upward, by more the more there are (see `nonpositive`).
+    pub fn with_positive_geometric(mut self, on: bool) -> Self {
+        self.positive_geometric = on;
+        if on && self.nonpositive() > 0 {
+            // Same accumulators as `build`, so all-positive data gives identical results
+            let positives = &self.data[self.nonpositive()..];
+            let (log_sum, logs) = positives
+                .par_iter()
+                .fold(
+                    || (CompensatedSum::default(), Welford::default()),
+                    |(mut sum, mut logs), &x| {
+                        let log = x.ln();
+                        sum.add(log);
+                        logs.push(log);
+                        (sum, logs)
+                    },
+                )
+                .reduce(
+                    || (CompensatedSum::default(), Welford::default()),
+                    |a, b| (a.0.merge(b.0), a.1.merge(b.1)),
+                );
+            if !positives.is_empty() {
+                self.geo_mean = (log_sum.total() / positives.len() as f64).exp();
+                self.geo_std_dev = logs.variance().sqrt().exp();
+            }
+        }
+        self
+    }
+
+    /// Number of values that are zero or negative, which the geometric and harmonic
+    /// statistics aren't defined for
+    pub fn nonpositive(&self) -> usize {
+        self.data.partition_point(|&x| x <= 0.0)
+    }
+
+    /// True when there are no (finite) values to summarize
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// True when every value is identical (including a single value), so dispersion is zero
+    /// and measures like the KDE or coefficient of variation are degenerate.
+    pub fn is_constant(&self) -> bool {
+        self.n > 0 && self.data[0] == self.data[self.n - 1]
+    }
+
+    /// Coefficient of variation (std dev / mean), a unitless measure of relative dispersion.
+    /// NaN when the mean is zero.
+    pub fn cv(&self) -> f64 {
+        if self.mean == 0.0 {
+            f64::NAN
+        } else {
+            self.std_dev / self.mean
         }
     }
 
-    /// Calculate quantile (0.0 = min, 0.5 = median, 1.0 = max)
+    /// Calculate quantile (0.0 = min, 0.5 = median, 1.0 = max), using `quantile_method`
+    /// between ranks
     pub fn quantile(&self, q: f64) -> f64 {
-        if self.data.is_empty() {
+        quantile_sorted(&self.data, q, self.quantile_method)
+    }
+
+    /// Inverse of `quantile`: the q at which `value` falls, interpolating linearly between
+    /// neighbouring values. A run of ties ranks at the middle of the run. Values below the
+    /// minimum give 0 and above the maximum give 1.
+    pub fn rank(&self, value: f64) -> f64 {
+        if self.n == 0 || value.is_nan() {
+            return f64::NAN;
+        }
+
+        let below = self.data.partition_point(|&x| x < value);
+        let through = self.data.partition_point(|&x| x <= value);
+        if through == 0 {
+            return 0.0;
+        }
+        if below == self.n {
+            return 1.0;
+        }
+        if self.n == 1 {
+            return 0.5;
+        }
+
+        let position = if through > below {
+            (below + through - 1) as f64 / 2.0
+        } else {
+            let (lower, upper) = (self.data[below - 1], self.data[below]);
+            (below - 1) as f64 + (value - lower) / (upper - lower)
+        };
+        position / (self.n - 1) as f64
+    }
+
+    /// Standard error of the mean: the sample standard deviation (n - 1 denominator) over √n.
+    /// NaN when there are fewer than two values, since one value says nothing about spread.
+    pub fn sem(&self) -> f64 {
+        if self.n < 2 {
             return f64::NAN;
         }
-        if q <= 0.0 {
-            return self.data[0];
+
+        let n = self.n as f64;
+        (self.variance * n / (n - 1.0)).sqrt() / n.sqrt()
+    }
+
+    /// Two-sided confidence interval for the mean at `level` (e.g. 0.95), using Student's t
+    /// with the sample standard deviation. NaN bounds when there are fewer than two values.
+    pub fn mean_ci(&self, level: f64) -> (f64, f64) {
+        if self.n < 2 {
+            return (f64::NAN, f64::NAN);
+        }
+
+        let t = student_t_quantile(0.5 + level / 2.0, self.n as f64 - 1.0);
+        let half_width = t * self.sem();
+        (self.mean - half_width, self.mean + half_width)
+    }
+
+    /// Bootstrap confidence interval for any statistic: recomputes `statistic` on `resamples`
+    /// resamples drawn with replacement and takes the central `level` of the results.
+    /// Each resample gets its own RNG derived from `seed`, so results are reproducible
+    /// regardless of how rayon schedules the work.
+    pub fn bootstrap_ci(
+        &self,
+        level: f64,
+        resamples: usize,
+        seed: u64,
+        statistic: impl Fn(&Stats) -> f64 + Sync,
+    ) -> (f64, f64) {
+        if self.n == 0 || resamples == 0 {
+            return (f64::NAN, f64::NAN);
+        }
+
+        let mut estimates: Vec<f64> = (0..resamples as u64)
+            .into_par_iter()
+            .map(|i| {
+                let mut rng =
+                    fastrand::Rng::with_seed(seed ^ i.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                let resample = (0..self.n)
+                    .map(|_| self.data[rng.usize(..self.n)])
+                    .collect();
+                statistic(
+                    &Stats::new(resample)
+                        .with_quantile_method(self.quantile_method)
+                        .with_positive_geometric(self.positive_geometric),
+                )
+            })
+            .collect();
+        sort_floats(&mut estimates);
+
+        let tail = (1.0 - level) / 2.0;
+        (
+            quantile_sorted(&estimates, tail, QuantileMethod::Linear),
+            quantile_sorted(&estimates, 1.0 - tail, QuantileMethod::Linear),
+        )
+    }
+
+    /// Interquartile range (75th minus 25th percentile)
+    pub fn iqr(&self) -> f64 {
+        self.quantile(0.75) - self.quantile(0.25)
+    }
+
+    /// Median absolute deviation from the median, a robust alternative to std dev
+    pub fn mad(&self) -> f64 {
+        let median = self.quantile(0.5);
+        let mut deviations: Vec<f64> = self.data.iter().map(|x| (x - median).abs()).collect();
+        sort_floats(&mut deviations);
+        quantile_sorted(&deviations, 0.5, self.quantile_method)
+    }
+
+    /// Mean after dropping `fraction` of the values from each end.
+    /// A fraction of 0 is the plain mean; fractions that would drop everything give the median.
+    pub fn trimmed_mean(&self, fraction: f64) -> f64 {
+        let cut = (self.n as f64 * fraction.max(0.0)).floor() as usize;
+        if 2 * cut >= self.n {
+            return self.quantile(0.5);
+        }
+
+        let kept = &self.data[cut..self.n - cut];
+        compensated_sum(kept.iter().copied()) / kept.len() as f64
+    }
+
+    /// Mean after clamping the lowest and highest `fraction` of values to the
+    /// `fraction` and `1 - fraction` quantiles (rather than dropping them, as trimming does)
+    pub fn winsorized_mean(&self, fraction: f64) -> f64 {
+        compensated_sum(self.winsorized(fraction)) / self.n as f64
+    }
+
+    /// Population variance of the winsorized data (see `winsorized_mean`)
+    pub fn winsorized_variance(&self, fraction: f64) -> f64 {
+        let mean = self.winsorized_mean(fraction);
+        compensated_sum(self.winsorized(fraction).map(|x| (x - mean).powi(2))) / self.n as f64
+    }
+
+    fn winsorized(&self, fraction: f64) -> impl Iterator<Item = f64> + '_ {
+        let (low, high) = (self.quantile(fraction), self.quantile(1.0 - fraction));
+        self.data.iter().map(move |x| x.clamp(low, high))
+    }
+
+    /// Tukey's fences: values outside [q25 - k·IQR, q75 + k·IQR] are outliers (k is usually 1.5)
+    pub fn outlier_fences(&self, k: f64) -> (f64, f64) {
+        let iqr = self.iqr();
+        (self.quantile(0.25) - k * iqr, self.quantile(0.75) + k * iqr)
+    }
+
+    /// Number of values outside the Tukey fences
+    pub fn outlier_count(&self, k: f64) -> usize {
+        let (below, above) = self.outliers(k);
+        below.len() + above.len()
+    }
+
+    /// Values below the lower and above the upper Tukey fence, each in ascending order
+    pub fn outliers(&self, k: f64) -> (&[f64], &[f64]) {
+        let (low, high) = self.outlier_fences(k);
+        let below = self.data.partition_point(|&x| x < low);
+        let inside = self.data.partition_point(|&x| x <= high);
+        (&self.data[..below], &self.data[inside..])
+    }
+
+    /// Empirical CDF as the corners of a step function: each value jumps from i/n to (i+1)/n
+    pub fn ecdf(&self) -> Vec<(f64, f64)> {
+        let n = self.n as f64;
+        self.data
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &x)| [(x, i as f64 / n), (x, (i + 1) as f64 / n)])
+            .collect()
+    }
+
+    /// Jackknife bias and standard error of `statistic` from the n leave-one-out values,
+    /// computed in parallel. NaN for fewer than two values.
+    pub fn jackknife(&self, statistic: JackknifeStatistic) -> Jackknife {
+        if self.n < 2 {
+            return Jackknife {
+                bias: f64::NAN,
+                std_error: f64::NAN,
+            };
+        }
+
+        let n = self.n as f64;
+        let m2 = self.variance * n;
+        let leave_one_out = |i: usize| {
+            let x = self.data[i];
+            match statistic {
+                JackknifeStatistic::Mean => (self.sum - x) / (n - 1.0),
+                JackknifeStatistic::Median => self.median_without(i),
+                JackknifeStatistic::StdDev => {
+                    let m2 = m2 - (x - self.mean).powi(2) * n / (n - 1.0);
+                    (m2.max(0.0) / (n - 1.0)).sqrt()
+                }
+            }
+        };
+        let full = match statistic {
+            JackknifeStatistic::Mean => self.mean,
+            JackknifeStatistic::Median => self.quantile(0.5),
+            JackknifeStatistic::StdDev => self.std_dev,
+        };
+
+        let estimates: Vec<f64> = (0..self.n).into_par_iter().map(leave_one_out).collect();
+        let mean = compensated_sum(estimates.iter().copied()) / n;
+        let squares = compensated_sum(estimates.iter().map(|e| (e - mean).powi(2)));
+        Jackknife {
+            bias: (n - 1.0) * (mean - full),
+            std_error: ((n - 1.0) / n * squares).sqrt(),
+        }
+    }
+
+    /// Median of the data with sorted index `skip` removed, without copying: takes the
+    /// n - 1 remaining values' middle rank, shifting indices at or past `skip` by one
+    fn median_without(&self, skip: usize) -> f64 {
+        let remaining = |k: usize| self.data[if k < skip { k } else { k + 1 }];
+        self.quantile_method
+            .at_rank(0.5 * (self.n - 2) as f64, remaining)
+    }
+
+    /// Counts values in `bins` equal-width bins spanning [min, max], returned as
+    /// (bin center, count). Bins are left-closed and right-open, [lower, upper), except the
+    /// last, which also includes max so every value is counted. Constant data gets one bin.
+    pub fn histogram(&self, bins: usize) -> Vec<(f64, usize)> {
+        let (Some(&min), Some(&max)) = (self.data.first(), self.data.last()) else {
+            return Vec::new();
+        };
+        let bins = if max > min { bins.max(1) } else { 1 };
+        let width = (max - min) / bins as f64;
+
+        // Data is sorted, so each edge's count of values below it is a binary search
+        let mut below = 0;
+        (0..bins)
+            .map(|i| {
+                let upper = if i + 1 == bins {
+                    self.n
+                } else {
+                    let edge = min + width * (i + 1) as f64;
+                    self.data.partition_point(|&x| x < edge)
+                };
+                let count = upper - below;
+                below = upper;
+                (min + width * (i as f64 + 0.5), count)
+            })
+            .collect()
+    }
+
+    /// Edges of geometrically spaced histogram bins, for data spanning orders of magnitude
+    /// (e.g. latencies). `Some(bins)` spaces that many bins evenly in log scale across
+    /// [min, max]; `None` gives one bin per decade, with edges at the powers of ten around
+    /// min and max. Returns None unless the data is positive.
+    pub fn log_bin_edges(&self, bins: Option<usize>) -> Option<Vec<f64>> {
+        let (Some(&min), Some(&max)) = (self.data.first(), self.data.last()) else {
+            return None;
+        };
+        if min <= 0.0 {
+            return None;
+        }
+
+        let Some(bins) = bins else {
+            // Powers of ten are computed exactly, so adjust log10's estimate by comparing them
+            let mut low = min.log10().floor() as i32;
+            while 10f64.powi(low) > min {
+                low -= 1;
+            }
+            while 10f64.powi(low + 1) <= min {
+                low += 1;
+            }
+            let mut high = (low + 1).max(max.log10().ceil() as i32);
+            while 10f64.powi(high - 1) >= max && high - 1 > low {
+                high -= 1;
+            }
+            while 10f64.powi(high) < max {
+                high += 1;
+            }
+            return Some((low..=high).map(|k| 10f64.powi(k)).collect());
+        };
+
+        let bins = if max > min { bins.max(1) } else { 1 };
+        let ratio = max / min;
+        let mut edges: Vec<f64> = (0..=bins)
+            .map(|i| min * ratio.powf(i as f64 / bins as f64))
+            .collect();
+        // Pin the outer edges so rounding can't leave min or max outside them
+        edges[0] = min;
+        edges[bins] = max;
+        Some(edges)
+    }
+
+    /// Counts the values in the bins between consecutive ascending `edges`, returned as
+    /// (lower, upper, count). Bins are left-closed like `histogram`'s, with the last also
+    /// including its upper edge; values outside the edges aren't counted.
+    pub fn bin_counts(&self, edges: &[f64]) -> Vec<(f64, f64, usize)> {
+        let mut below = self.data.partition_point(|&x| x < edges[0]);
+        edges
+            .windows(2)
+            .enumerate()
+            .map(|(i, edge)| {
+                let upper = if i + 2 == edges.len() {
+                    self.data.partition_point(|&x| x <= edge[1])
+                } else {
+                    self.data.partition_point(|&x| x < edge[1])
+                };
+                let count = upper - below;
+                below = upper;
+                (edge[0], edge[1], count)
+            })
+            .collect()
+    }
+}
+
+/// Statistics `Stats::jackknife` can estimate, each with an O(1) leave-one-out recompute
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum JackknifeStatistic {
+    /// Downdates the sum: (Σx - xᵢ) / (n - 1)
+    Mean,
+    /// Re-interpolates the middle rank of the sorted data with index i skipped. The jackknife
+    /// is known to be unreliable for the median (its leave-one-out values take at most two or
+    /// three distinct values), so treat the standard error as rough
+    Median,
+    /// Population std dev, downdating Welford's M2 by (xᵢ - mean)² · n / (n - 1)
+    #[value(name = "std_dev")]
+    StdDev,
+}
+
+/// Jackknife estimates for a statistic
+#[derive(Debug)]
+pub struct Jackknife {
+    /// (n - 1)(mean of the leave-one-out values - full-sample value); subtract to correct
+    pub bias: f64,
+    /// √((n - 1)/n · Σ(leave-one-out value - their mean)²)
+    pub std_error: f64,
+}
+
+/// Sturges' rule, ⌈log₂ n⌉ + 1 bins: a conservative default for roughly normal data
+pub fn sturges_bins(n: usize) -> usize {
+    (n.max(1) as f64).log2().ceil() as usize + 1
+}
+
+/// Result of a two-sided Mann-Whitney U test
+#[derive(Debug)]
+pub struct MannWhitney {
+    /// U statistic for the first sample: how many (a, b) pairs have a > b, ties counting half
+    pub u: f64,
+    /// Standardized U under the normal approximation
+    pub z: f64,
+    /// Two-sided p-value for "the distributions differ"
+    pub p_value: f64,
+}
+
+/// Rank-based test of whether two samples come from the same distribution, with no
+/// normality assumption. Ties get the average (mid) rank of their run, and the variance is
+/// tie-corrected. The p-value uses the normal approximation with a continuity correction,
+/// which is accurate once both samples have more than about 20 values.
+pub fn mann_whitney(a: &Stats, b: &Stats) -> MannWhitney {
+    let (n1, n2) = (a.n as f64, b.n as f64);
+    let n = n1 + n2;
+
+    // Both samples are sorted, so merging gives ranks directly
+    let (mut i, mut j) = (0, 0);
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    while i < a.n || j < b.n {
+        let x = match (a.data.get(i), b.data.get(j)) {
+            (Some(&x), Some(&y)) => x.min(y),
+            (Some(&x), None) => x,
+            (None, Some(&y)) => y,
+            (None, None) => unreachable!(),
+        };
+        let ties_a = a.data[i..].partition_point(|&v| v == x);
+        let ties_b = b.data[j..].partition_point(|&v| v == x);
+        let ties = (ties_a + ties_b) as f64;
+
+        let first_rank = (i + j + 1) as f64;
+        rank_sum_a += ties_a as f64 * (first_rank + (ties - 1.0) / 2.0);
+        tie_term += ties.powi(3) - ties;
+        i += ties_a;
+        j += ties_b;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance.is_nan() || variance <= 0.0 {
+        return MannWhitney {
+            u,
+            z: 0.0,
+            p_value: 1.0,
+        };
+    }
+
+    let deviation = ((u - mean).abs() - 0.5).max(0.0);
+    let z = deviation.copysign(u - mean) / variance.sqrt();
+    MannWhitney {
+        u,
+        z,
+        p_value: (2.0 * (1.0 - normal_cdf(z.abs()))).min(1.0),
+    }
+}
+
+/// CDF of the standard normal distribution
+pub fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function, via the Chebyshev fit from Numerical Recipes
+/// (fractional error below 1.2e-7 everywhere)
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let poly = -x * x - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * poly.exp();
+    if x >= 0.0 { result } else { 2.0 - result }
+}
+
+/// Quantile function of the standard normal distribution, via Acklam's rational
+/// approximation (relative error below 1.2e-9 across (0, 1))
+pub fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239e0,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838e0,
+        -2.549732539343734e0,
+        4.374664141464968e0,
+        2.938163982698783e0,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996e0,
+        3.754408661907416e0,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    // Tails use a rational function in sqrt(-2 ln p), the center one in (p - 0.5)
+    let tail = |p: f64| {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < P_LOW {
+        tail(p)
+    } else if p > 1.0 - P_LOW {
+        -tail(1.0 - p)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Quantile function of Student's t distribution with `df` degrees of freedom.
+/// Exact for 1 and 2 degrees of freedom; otherwise a Cornish-Fisher expansion around the
+/// normal quantile, accurate to about 1e-3 at df = 3 and improving quickly with df.
+pub fn student_t_quantile(p: f64, df: f64) -> f64 {
+    if df == 1.0 {
+        return (std::f64::consts::PI * (p - 0.5)).tan();
+    }
+    if df == 2.0 {
+        return (2.0 * p - 1.0) / (2.0 * p * (1.0 - p)).sqrt();
+    }
+
+    let z = inverse_normal_cdf(p);
+    let z2 = z * z;
+    let g1 = (z2 + 1.0) * z / 4.0;
+    let g2 = ((5.0 * z2 + 16.0) * z2 + 3.0) * z / 96.0;
+    let g3 = (((3.0 * z2 + 19.0) * z2 + 17.0) * z2 - 15.0) * z / 384.0;
+    let g4 = ((((79.0 * z2 + 776.0) * z2 + 1482.0) * z2 - 1920.0) * z2 - 945.0) * z / 92160.0;
+    z + g1 / df + g2 / df.powi(2) + g3 / df.powi(3) + g4 / df.powi(4)
+}
+
+/// Sums with Neumaier's compensation, carrying the low-order bits that plain addition drops
+/// (e.g. adding many tiny values to a large total)
+fn compensated_sum(values: impl Iterator<Item = f64>) -> f64 {
+    values
+        .fold(CompensatedSum::default(), |mut acc, x| {
+            acc.add(x);
+            acc
+        })
+        .total()
+}
+
+/// Count and sum of a stream of values, for when nothing else is needed and keeping the
+/// values to sort them would be wasted work. Skips non-finite values like `Stats` does.
+/// Partial totals from separate threads can be merged.
+#[derive(Clone, Copy, Default)]
+pub struct Totals {
+    pub count: usize,
+    pub non_finite: usize,
+    sum: CompensatedSum,
+}
+
+impl Totals {
+    pub fn push(&mut self, x: f64) {
+        if x.is_finite() {
+            self.count += 1;
+            self.sum.add(x);
+        } else {
+            self.non_finite += 1;
+        }
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Totals {
+            count: self.count + other.count,
+            non_finite: self.non_finite + other.non_finite,
+            sum: self.sum.merge(other.sum),
+        }
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum.total()
+    }
+
+    /// NaN when there are no values, like `Stats::mean`
+    pub fn mean(&self) -> f64 {
+        self.sum() / self.count as f64
+    }
+}
+
+/// Replaces each value with the running total up to and including it, in the order given,
+/// for inputs that are increments. Non-finite values stay in place for `Stats` to count and
+/// drop, and don't add to the total.
+pub fn cumulate(data: &mut [f64]) {
+    let mut total = CompensatedSum::default();
+    for x in data.iter_mut().filter(|x| x.is_finite()) {
+        total.add(*x);
+        *x = total.total();
+    }
+}
+
+/// Running Neumaier-compensated sum; partial sums from separate threads can be merged
+#[derive(Clone, Copy, Default)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, x: f64) {
+        let t = self.sum + x;
+        if self.sum.abs() >= x.abs() {
+            self.compensation += (self.sum - t) + x;
+        } else {
+            self.compensation += (x - t) + self.sum;
+        }
+        self.sum = t;
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.add(other.sum);
+        self.compensation += other.compensation;
+        self
+    }
+
+    fn total(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+/// Running mean and M2 (sum of squared deviations) by Welford's algorithm: tracking the
+/// running mean keeps deviations small, so large offsets like 1e9 + ε don't swamp the
+/// variance the way Σx² - n·mean² does. Partial results combine with Chan et al.'s update.
+#[derive(Clone, Copy, Default)]
+struct Welford {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn merge(self, other: Self) -> Self {
+        if self.count == 0 {
+            return other;
+        }
+        if other.count == 0 {
+            return self;
+        }
+
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = (self.count * other.count) as f64 / count as f64;
+        Welford {
+            count,
+            mean: self.mean + delta * other.count as f64 / count as f64,
+            m2: self.m2 + other.m2 + delta * delta * weight,
+        }
+    }
+
+    /// Population variance; NaN for no data
+    fn variance(&self) -> f64 {
+        if self.count == 0 {
+            f64::NAN
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+}
+
+/// Partial accumulators for `Stats::new`'s single parallel pass over the data
+#[derive(Clone, Copy)]
+struct Moments {
+    count: usize,
+    sum: CompensatedSum,
+    log_sum: CompensatedSum,
+    reciprocal_sum: CompensatedSum,
+    values: Welford,
+    logs: Welford,
+    all_positive: bool,
+}
+
+impl Default for Moments {
+    fn default() -> Self {
+        Moments {
+            count: 0,
+            sum: CompensatedSum::default(),
+            log_sum: CompensatedSum::default(),
+            reciprocal_sum: CompensatedSum::default(),
+            values: Welford::default(),
+            logs: Welford::default(),
+            all_positive: true,
+        }
+    }
+}
+
+impl Moments {
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        self.sum.add(x);
+        self.values.push(x);
+
+        // Geometric and harmonic statistics are only defined for all-positive data
+        if x > 0.0 {
+            if self.all_positive {
+                let log = x.ln();
+                self.log_sum.add(log);
+                self.logs.push(log);
+                self.reciprocal_sum.add(x.recip());
+            }
+        } else {
+            self.all_positive = false;
         }
-        if q >= 1.0 {
-            return self.data[self.n - 1];
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Moments {
+            count: self.count + other.count,
+            sum: self.sum.merge(other.sum),
+            log_sum: self.log_sum.merge(other.log_sum),
+            reciprocal_sum: self.reciprocal_sum.merge(other.reciprocal_sum),
+            values: self.values.merge(other.values),
+            logs: self.logs.merge(other.logs),
+            all_positive: self.all_positive && other.all_positive,
         }
+    }
+
+    /// Population variance; NaN for no data
+    fn variance(&self) -> f64 {
+        self.values.variance()
+    }
+}
+
+/// How a quantile that falls between two ranks is resolved, following NumPy's method names.
+/// For q of n sorted values the (0-based) rank is q × (n - 1); when that's a whole number
+/// every method gives the value at it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum QuantileMethod {
+    /// Interpolate linearly between the neighbouring ranks (R's type 7, NumPy's default)
+    #[default]
+    Linear,
+    /// The value at the closest rank, with exact halves going to the even rank
+    Nearest,
+    /// The value at the rank below
+    Lower,
+    /// The value at the rank above
+    Higher,
+}
 
-        // Linear interpolation between closest ranks
-        let rank = q * (self.n - 1) as f64;
+impl QuantileMethod {
+    /// The value at fractional `rank`, where `at(k)` gives the k-th smallest value
+    fn at_rank(self, rank: f64, at: impl Fn(usize) -> f64) -> f64 {
         let lower = rank.floor() as usize;
-        let upper = rank.ceil() as usize;
         let fraction = rank - lower as f64;
+        if fraction == 0.0 {
+            return at(lower);
+        }
 
-        self.data[lower] * (1.0 - fraction) + self.data[upper] * fraction
+        match self {
+            QuantileMethod::Linear => at(lower) * (1.0 - fraction) + at(lower + 1) * fraction,
+            QuantileMethod::Nearest => at(rank.round_ties_even() as usize),
+            QuantileMethod::Lower => at(lower),
+            QuantileMethod::Higher => at(lower + 1),
+        }
+    }
+}
+
+/// Quantile of already-sorted data, resolving ranks in between values with `method`
+fn quantile_sorted(data: &[f64], q: f64, method: QuantileMethod) -> f64 {
+    if data.is_empty() {
+        return f64::NAN;
+    }
+    if q <= 0.0 {
+        return data[0];
+    }
+    if q >= 1.0 {
+        return data[data.len() - 1];
     }
+
+    method.at_rank(q * (data.len() - 1) as f64, |k| data[k])
 }
 
 #[cfg(test)]
@@ -84,6 +931,38 @@ mod tests {
         assert_eq!(stats.data, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
     }
 
+    #[test]
+    fn test_from_sorted_matches_new() {
+        let data = vec![1.0, 2.0, f64::NAN, 2.0, 5.0, 9.0];
+        let sorted = Stats::from_sorted(data.clone());
+        let stats = Stats::new(data);
+
+        assert_eq!(sorted.data, stats.data);
+        assert_eq!(sorted.non_finite, 1);
+        assert_eq!(sorted.mean, stats.mean);
+        assert_eq!(sorted.quantile(0.5), stats.quantile(0.5));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "isn't sorted")]
+    fn test_from_sorted_checks_order_in_debug() {
+        Stats::from_sorted(vec![2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_parallel_sort_matches_sequential() {
+        // Large enough for rayon to split, with plenty of duplicates and negative values
+        let data: Vec<f64> = (0..100_000u64)
+            .map(|i| ((i * 7919) % 1013) as f64 - 500.0)
+            .collect();
+        let mut expected = data.clone();
+        expected.sort_by(f64::total_cmp);
+
+        let stats = Stats::new(data);
+        assert_eq!(stats.data, expected);
+    }
+
     #[test]
     fn test_stats_variance_and_stddev() {
         let data = vec![2.0, 4.0, 6.0, 8.0, 10.0];
@@ -107,6 +986,245 @@ mod tests {
         assert!((stats.geo_mean - expected_gmean).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_histogram_edges_left_closed() {
+        // Bins [0, 2.5), [2.5, 5), [5, 7.5), [7.5, 10]
+        let stats = Stats::new(vec![0.0, 1.0, 2.5, 4.9, 5.0, 7.5, 9.0, 10.0]);
+        assert_eq!(
+            stats.histogram(4),
+            vec![(1.25, 2), (3.75, 2), (6.25, 1), (8.75, 3)]
+        );
+    }
+
+    #[test]
+    fn test_histogram_counts_everything() {
+        let stats = Stats::new((0..1000).map(|i| (i as f64).sqrt()).collect());
+        let histogram = stats.histogram(sturges_bins(stats.n));
+        assert_eq!(histogram.len(), 11);
+        assert_eq!(histogram.iter().map(|&(_, c)| c).sum::<usize>(), 1000);
+    }
+
+    #[test]
+    fn test_histogram_degenerate() {
+        assert_eq!(Stats::new(vec![3.0; 5]).histogram(10), vec![(3.0, 5)]);
+        assert!(Stats::new(Vec::new()).histogram(10).is_empty());
+        assert_eq!(sturges_bins(1), 1);
+        assert_eq!(sturges_bins(100), 8);
+    }
+
+    #[test]
+    fn test_log_bin_edges_per_decade() {
+        let stats = Stats::new(vec![3.0, 40.0, 100.0, 2500.0]);
+        assert_eq!(
+            stats.log_bin_edges(None).unwrap(),
+            vec![1.0, 10.0, 100.0, 1000.0, 10000.0]
+        );
+        assert_eq!(
+            stats.bin_counts(&stats.log_bin_edges(None).unwrap()),
+            vec![
+                (1.0, 10.0, 1),
+                (10.0, 100.0, 1),
+                (100.0, 1000.0, 1),
+                (1000.0, 10000.0, 1)
+            ]
+        );
+
+        // Exact powers of ten at both ends don't add empty decades
+        let stats = Stats::new(vec![0.001, 0.5, 1.0]);
+        assert_eq!(
+            stats.log_bin_edges(None).unwrap(),
+            vec![0.001, 0.01, 0.1, 1.0]
+        );
+        let counts = stats.bin_counts(&stats.log_bin_edges(None).unwrap());
+        assert_eq!(
+            counts.iter().map(|&(_, _, c)| c).collect::<Vec<_>>(),
+            [1, 0, 2]
+        );
+
+        assert_eq!(
+            Stats::new(vec![5.0; 3]).log_bin_edges(None).unwrap(),
+            vec![1.0, 10.0]
+        );
+    }
+
+    #[test]
+    fn test_log_bin_edges_geometric() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 5.0, 9.0, 16.0]);
+        let edges = stats.log_bin_edges(Some(4)).unwrap();
+        for (edge, want) in edges.iter().zip([1.0, 2.0, 4.0, 8.0, 16.0]) {
+            assert!((edge - want).abs() < 1e-12, "{edge} != {want}");
+        }
+        let counts: Vec<usize> = stats.bin_counts(&edges).iter().map(|b| b.2).collect();
+        assert_eq!(counts, [1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_log_bin_edges_need_positive_data() {
+        assert!(Stats::new(vec![0.0, 1.0]).log_bin_edges(None).is_none());
+        assert!(Stats::new(vec![-1.0, 1.0]).log_bin_edges(Some(3)).is_none());
+        assert!(Stats::new(Vec::new()).log_bin_edges(None).is_none());
+    }
+
+    #[test]
+    fn test_totals_match_stats() {
+        let data: Vec<f64> = (0..10_000).map(|i| 1e9 + (i % 7) as f64 * 0.1).collect();
+        let stats = Stats::new(data.clone());
+
+        let (left, right) = data.split_at(3000);
+        let mut totals = [Totals::default(), Totals::default()];
+        for (half, values) in totals.iter_mut().zip([left, right]) {
+            for &x in values {
+                half.push(x);
+            }
+        }
+        let totals = totals[0].merge(totals[1]);
+
+        assert_eq!(totals.count, stats.n);
+        assert!((totals.sum() - stats.sum).abs() <= 1e-15 * stats.sum);
+        assert!((totals.mean() - stats.mean).abs() <= 1e-15 * stats.mean);
+    }
+
+    #[test]
+    fn test_totals_skip_non_finite() {
+        let mut totals = Totals::default();
+        for x in [1.0, f64::NAN, 2.0, f64::INFINITY] {
+            totals.push(x);
+        }
+        assert_eq!((totals.count, totals.non_finite), (2, 2));
+        assert_eq!(totals.sum(), 3.0);
+        assert!(Totals::default().mean().is_nan());
+    }
+
+    #[test]
+    fn test_cumulate() {
+        let mut data = vec![3.0, 1.0, -2.0, 0.0, 5.0];
+        cumulate(&mut data);
+        assert_eq!(data, vec![3.0, 4.0, 2.0, 2.0, 7.0]);
+
+        let mut data = vec![1.0, f64::NAN, 2.0];
+        cumulate(&mut data);
+        assert_eq!(data[0], 1.0);
+        assert!(data[1].is_nan());
+        assert_eq!(data[2], 3.0);
+
+        // Compensated, so many small increments don't drift
+        let mut data = vec![0.1; 1000];
+        cumulate(&mut data);
+        assert!((data[999] - 100.0).abs() < 1e-12);
+    }
+
+    /// Reference jackknife that recomputes the statistic on each leave-one-out copy
+    fn naive_jackknife(data: &[f64], statistic: impl Fn(&Stats) -> f64) -> (f64, f64) {
+        let n = data.len() as f64;
+        let full = statistic(&Stats::new(data.to_vec()));
+        let estimates: Vec<f64> = (0..data.len())
+            .map(|i| {
+                let mut copy = data.to_vec();
+                copy.remove(i);
+                statistic(&Stats::new(copy))
+            })
+            .collect();
+        let mean = estimates.iter().sum::<f64>() / n;
+        let squares: f64 = estimates.iter().map(|e| (e - mean).powi(2)).sum();
+        ((n - 1.0) * (mean - full), ((n - 1.0) / n * squares).sqrt())
+    }
+
+    #[test]
+    fn test_jackknife_matches_naive_recompute() {
+        let data: Vec<f64> = (0..41)
+            .map(|i| ((i * 37) % 41) as f64 * 1.5 + 3.0)
+            .collect();
+        let odd = Stats::new(data.clone());
+        let even = Stats::new(data[..40].to_vec());
+        type Reference = fn(&Stats) -> f64;
+        let cases: [(JackknifeStatistic, Reference); 3] = [
+            (JackknifeStatistic::Mean, |s| s.mean),
+            (JackknifeStatistic::Median, |s| s.quantile(0.5)),
+            (JackknifeStatistic::StdDev, |s| s.std_dev),
+        ];
+
+        for stats in [&odd, &even] {
+            for (statistic, reference) in cases {
+                let jackknife = stats.jackknife(statistic);
+                let (bias, std_error) = naive_jackknife(&stats.data, reference);
+                assert!((jackknife.bias - bias).abs() < 1e-9, "{statistic:?} bias");
+                assert!(
+                    (jackknife.std_error - std_error).abs() < 1e-9,
+                    "{statistic:?} SE"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_jackknife_median_follows_quantile_method() {
+        let data: Vec<f64> = (0..40).map(|i| ((i * 37) % 41) as f64 * 1.5).collect();
+        type Reference = fn(&Stats) -> f64;
+        let cases: [(QuantileMethod, Reference); 3] = [
+            (QuantileMethod::Nearest, |s| {
+                quantile_sorted(&s.data, 0.5, QuantileMethod::Nearest)
+            }),
+            (QuantileMethod::Lower, |s| {
+                quantile_sorted(&s.data, 0.5, QuantileMethod::Lower)
+            }),
+            (QuantileMethod::Higher, |s| {
+                quantile_sorted(&s.data, 0.5, QuantileMethod::Higher)
+            }),
+        ];
+
+        for (method, reference) in cases {
+            let stats = Stats::new(data.clone()).with_quantile_method(method);
+            let jackknife = stats.jackknife(JackknifeStatistic::Median);
+            let (bias, std_error) = naive_jackknife(&stats.data, reference);
+            assert!((jackknife.bias - bias).abs() < 1e-9, "{method:?} bias");
+            assert!(
+                (jackknife.std_error - std_error).abs() < 1e-9,
+                "{method:?} SE"
+            );
+        }
+    }
+
+    #[test]
+    fn test_jackknife_mean() {
+        // The mean is unbiased, and its jackknife SE equals the usual standard error
+        let stats = Stats::new(vec![2.0, 4.0, 6.0, 8.0, 10.0]);
+        let jackknife = stats.jackknife(JackknifeStatistic::Mean);
+        assert!(jackknife.bias.abs() < 1e-12);
+        assert!((jackknife.std_error - stats.sem()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_jackknife_too_few_values() {
+        let jackknife = Stats::new(vec![1.0]).jackknife(JackknifeStatistic::StdDev);
+        assert!(jackknife.bias.is_nan());
+        assert!(jackknife.std_error.is_nan());
+    }
+
+    #[test]
+    fn test_stats_sem() {
+        // Sample variance of 2, 4, 6, 8, 10 is 40 / 4 = 10, so sem = √10 / √5 = √2
+        let stats = Stats::new(vec![2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert!((stats.sem() - 2.0_f64.sqrt()).abs() < 1e-12);
+
+        assert!(Stats::new(vec![5.0]).sem().is_nan());
+        assert!(Stats::new(Vec::new()).sem().is_nan());
+        assert_eq!(Stats::new(vec![3.0, 3.0]).sem(), 0.0);
+    }
+
+    #[test]
+    fn test_stats_geometric_std_dev() {
+        // ln values are 0, ln 2, 2 ln 2, 3 ln 2: mean 1.5 ln 2, population variance
+        // (2.25 + 0.25 + 0.25 + 2.25) / 4 · (ln 2)² = 1.25 (ln 2)², so gsd = 2^√1.25 ≈ 2.17
+        let stats = Stats::new(vec![1.0, 2.0, 4.0, 8.0]);
+        let expected = 2.0_f64.powf(1.25_f64.sqrt());
+        assert!((stats.geo_std_dev - expected).abs() < 1e-10);
+
+        // Constant data has no multiplicative spread
+        assert!((Stats::new(vec![3.0; 4]).geo_std_dev - 1.0).abs() < 1e-12);
+        assert!(Stats::new(vec![0.0, 1.0, 2.0]).geo_std_dev.is_nan());
+        assert!(Stats::new(vec![-1.0, 1.0]).geo_std_dev.is_nan());
+    }
+
     #[test]
     fn test_stats_geometric_mean_with_zero() {
         let data = vec![0.0, 1.0, 2.0, 3.0];
@@ -116,6 +1234,21 @@ mod tests {
         assert!(stats.geo_mean.is_nan());
     }
 
+    #[test]
+    fn test_stats_geometric_mean_positive_only() {
+        let stats = Stats::new(vec![-3.0, 0.0, 2.0, 8.0]);
+        assert_eq!(stats.nonpositive(), 2);
+        assert!(stats.geo_mean.is_nan());
+
+        let stats = stats.with_positive_geometric(true);
+        assert!((stats.geo_mean - 4.0).abs() < 1e-10);
+        assert!((stats.geo_std_dev - 2.0).abs() < 1e-10);
+
+        // Nothing left to average
+        let stats = Stats::new(vec![-1.0, 0.0]).with_positive_geometric(true);
+        assert!(stats.geo_mean.is_nan());
+    }
+
     #[test]
     fn test_stats_geometric_mean_with_negative() {
         let data = vec![-1.0, 1.0, 2.0, 3.0];
@@ -125,6 +1258,51 @@ mod tests {
         assert!(stats.geo_mean.is_nan());
     }
 
+    #[test]
+    fn test_stats_harmonic_mean() {
+        let data = vec![1.0, 2.0, 4.0];
+        let stats = Stats::new(data);
+
+        // Harmonic mean = 3 / (1 + 1/2 + 1/4) = 3 / 1.75
+        assert!((stats.harmonic_mean - 3.0 / 1.75).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_stats_harmonic_mean_rates() {
+        // Averaging 60 and 40 (e.g. km/h over equal distances) gives 48, not 50
+        let stats = Stats::new(vec![60.0, 40.0]);
+        assert!((stats.harmonic_mean - 48.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_stats_harmonic_mean_with_zero() {
+        let stats = Stats::new(vec![0.0, 1.0, 2.0]);
+        assert!(stats.harmonic_mean.is_nan());
+    }
+
+    #[test]
+    fn test_stats_harmonic_mean_with_negative() {
+        let stats = Stats::new(vec![-1.0, 1.0, 2.0]);
+        assert!(stats.harmonic_mean.is_nan());
+    }
+
+    #[test]
+    fn test_stats_cv() {
+        // Mean 6, std dev √8
+        let stats = Stats::new(vec![2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert!((stats.cv() - 8.0_f64.sqrt() / 6.0).abs() < 1e-10);
+
+        // Scale invariant
+        let scaled = Stats::new(vec![2e3, 4e3, 6e3, 8e3, 10e3]);
+        assert!((scaled.cv() - stats.cv()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_stats_cv_zero_mean() {
+        let stats = Stats::new(vec![-1.0, 1.0]);
+        assert!(stats.cv().is_nan());
+    }
+
     #[test]
     fn test_quantile_min() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
@@ -164,6 +1342,50 @@ mod tests {
         assert!((q75 - 4.0).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_quantile_methods() {
+        let values = vec![1.0, 2.0, 3.0, 4.0, 10.0];
+        let quantiles = |method| {
+            let stats = Stats::new(values.clone()).with_quantile_method(method);
+            // Ranks 0.4, 1.5 (a tie for nearest), 1.6, 3.8, and a whole-number rank 1
+            [0.1, 0.375, 0.4, 0.95, 0.25].map(|q| stats.quantile(q))
+        };
+
+        let linear = quantiles(QuantileMethod::Linear);
+        let expected = [1.4, 2.5, 2.6, 8.8, 2.0];
+        for (got, want) in linear.iter().zip(expected) {
+            assert!((got - want).abs() < 1e-10, "{got} != {want}");
+        }
+        assert_eq!(
+            quantiles(QuantileMethod::Nearest),
+            [1.0, 3.0, 3.0, 10.0, 2.0]
+        );
+        assert_eq!(quantiles(QuantileMethod::Lower), [1.0, 2.0, 2.0, 4.0, 2.0]);
+        assert_eq!(
+            quantiles(QuantileMethod::Higher),
+            [2.0, 3.0, 3.0, 10.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn test_quantile_method_extremes() {
+        for method in [
+            QuantileMethod::Nearest,
+            QuantileMethod::Lower,
+            QuantileMethod::Higher,
+        ] {
+            let stats = Stats::new(vec![3.0, 1.0, 2.0]).with_quantile_method(method);
+            assert_eq!(stats.quantile(0.0), 1.0);
+            assert_eq!(stats.quantile(1.0), 3.0);
+            assert!(
+                Stats::new(Vec::new())
+                    .with_quantile_method(method)
+                    .quantile(0.5)
+                    .is_nan()
+            );
+        }
+    }
+
     #[test]
     fn test_quantile_even_number_of_values() {
         let data = vec![1.0, 2.0, 3.0, 4.0];
@@ -182,6 +1404,50 @@ mod tests {
         assert!(stats.quantile(0.5).is_nan());
     }
 
+    #[test]
+    fn test_empty_stats_are_nan() {
+        let stats = Stats::new(vec![]);
+
+        assert!(stats.is_empty());
+        assert_eq!(stats.n, 0);
+        assert_eq!(stats.sum, 0.0);
+        for value in [
+            stats.mean,
+            stats.geo_mean,
+            stats.harmonic_mean,
+            stats.variance,
+            stats.std_dev,
+            stats.cv(),
+            stats.quantile(0.0),
+            stats.quantile(0.25),
+            stats.quantile(1.0),
+            stats.quantile(f64::NAN),
+            stats.iqr(),
+            stats.mad(),
+            stats.trimmed_mean(0.1),
+            stats.winsorized_mean(0.1),
+            stats.rank(1.0),
+        ] {
+            assert!(value.is_nan());
+        }
+        assert_eq!(stats.outlier_count(1.5), 0);
+        assert!(stats.ecdf().is_empty());
+    }
+
+    #[test]
+    fn test_single_value_stats() {
+        let stats = Stats::new(vec![7.0]);
+
+        assert!(!stats.is_empty());
+        assert_eq!(stats.mean, 7.0);
+        assert_eq!(stats.variance, 0.0);
+        assert_eq!(stats.iqr(), 0.0);
+        assert_eq!(stats.mad(), 0.0);
+        assert_eq!(stats.trimmed_mean(0.4), 7.0);
+        assert_eq!(stats.outlier_count(1.5), 0);
+        assert_eq!(stats.quantile(0.99), 7.0);
+    }
+
     #[test]
     fn test_quantile_single_value() {
         let data = vec![42.0];
@@ -234,6 +1500,337 @@ mod tests {
         assert_eq!(stats.quantile(1.0), 5.0);
     }
 
+    #[test]
+    fn test_stats_is_constant() {
+        assert!(Stats::new(vec![5.0, 5.0, 5.0]).is_constant());
+        assert!(Stats::new(vec![42.0]).is_constant());
+        assert!(!Stats::new(vec![5.0, 5.0, 6.0]).is_constant());
+        assert!(!Stats::new(vec![]).is_constant());
+    }
+
+    #[test]
+    fn test_iqr() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(stats.iqr(), 2.0);
+    }
+
+    #[test]
+    fn test_mad() {
+        // Median 3, absolute deviations [2, 1, 0, 1, 97] -> median 1
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+        assert_eq!(stats.mad(), 1.0);
+    }
+
+    #[test]
+    fn test_trimmed_mean() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 1000.0]);
+
+        // 10% from each end drops 1.0 and 1000.0
+        assert_eq!(stats.trimmed_mean(0.1), 5.5);
+        assert_eq!(stats.trimmed_mean(0.0), stats.mean);
+    }
+
+    #[test]
+    fn test_trimmed_mean_too_large_fraction() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 10.0]);
+        assert_eq!(stats.trimmed_mean(0.5), 2.5);
+        assert_eq!(stats.trimmed_mean(0.9), 2.5);
+    }
+
+    #[test]
+    fn test_outlier_count() {
+        // q25 = 1.75, q75 = 5.5, IQR = 3.75 -> fences at [-3.875, 11.125]
+        let stats = Stats::new(vec![-5.0, 1.0, 2.0, 3.0, 4.0, 5.0, 7.0, 20.0]);
+        assert_eq!(stats.outlier_fences(1.5), (-3.875, 11.125));
+        assert_eq!(stats.outlier_count(1.5), 2);
+        assert_eq!(stats.outlier_count(10.0), 0);
+
+        let (below, above) = stats.outliers(1.5);
+        assert_eq!(below, &[-5.0]);
+        assert_eq!(above, &[20.0]);
+    }
+
+    #[test]
+    fn test_winsorized_zero_fraction() {
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+        assert_eq!(stats.winsorized_mean(0.0), stats.mean);
+        assert!((stats.winsorized_variance(0.0) - stats.variance).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_winsorized_skewed() {
+        // 20% clamps to the 0.2 and 0.8 quantiles: 2.8 and 8.2
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 1000.0];
+        let stats = Stats::new(data);
+        let (low, high) = (stats.quantile(0.2), stats.quantile(0.8));
+
+        let clamped: Vec<f64> = stats.data.iter().map(|x| x.clamp(low, high)).collect();
+        let expected = clamped.iter().sum::<f64>() / 10.0;
+        assert!((stats.winsorized_mean(0.2) - expected).abs() < 1e-9);
+
+        // Clamping the long tail pulls both toward the center
+        assert!(stats.winsorized_mean(0.2) < stats.mean);
+        assert!(stats.winsorized_variance(0.2) < stats.variance);
+    }
+
+    #[test]
+    fn test_compensated_sum() {
+        // Each 1e-16 is below half an ulp of 1.0, so naive addition drops all of them
+        let values = || std::iter::once(1.0).chain(std::iter::repeat_n(1e-16, 1_000_000));
+        let exact = 1.0 + 1e-10;
+
+        let naive: f64 = values().sum();
+        let compensated = compensated_sum(values());
+        assert_eq!(naive, 1.0);
+        assert!((compensated - exact).abs() < (naive - exact).abs());
+        assert!((compensated - exact).abs() < 1e-20);
+
+        // Catastrophic cancellation that also defeats plain Kahan summation
+        assert_eq!(compensated_sum([1.0, 1e100, 1.0, -1e100].into_iter()), 2.0);
+    }
+
+    #[test]
+    fn test_parallel_moments_match_sequential() {
+        let data: Vec<f64> = (0..200_000).map(|i| 1e9 + (i % 97) as f64 * 0.25).collect();
+        let stats = Stats::new(data.clone());
+
+        let mut sequential = Moments::default();
+        for &x in &stats.data {
+            sequential.push(x);
+        }
+        let n = data.len() as f64;
+        let variance = sequential.variance();
+
+        assert!((stats.sum - sequential.sum.total()).abs() < 1e-6);
+        assert!((stats.variance - variance).abs() < 1e-6 * variance);
+
+        // Both agree with the variance of the offset-free values
+        let shifted = Stats::new(data.iter().map(|x| x - 1e9).collect());
+        assert!((stats.variance - shifted.variance).abs() < 1e-6 * shifted.variance);
+        assert!((stats.geo_mean - (sequential.log_sum.total() / n).exp()).abs() < 1e-3);
+        assert!((stats.harmonic_mean - n / sequential.reciprocal_sum.total()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_moments_merge_with_empty() {
+        let mut moments = Moments::default();
+        moments.push(2.0);
+        moments.push(4.0);
+        let merged = Moments::default().merge(moments).merge(Moments::default());
+        assert_eq!(merged.count, 2);
+        assert_eq!(merged.values.mean, 3.0);
+        assert_eq!(merged.variance(), 1.0);
+        assert!(Moments::default().variance().is_nan());
+    }
+
+    #[test]
+    fn test_variance_large_offset() {
+        // Deviations of 4, 7, 13, 16 around 10 give a variance of 22.5 at any offset
+        let data: Vec<f64> = [4.0, 7.0, 13.0, 16.0].iter().map(|x| 1e9 + x).collect();
+
+        // The textbook one-pass formula loses every significant digit here
+        let n = data.len() as f64;
+        let naive =
+            data.iter().map(|x| x * x).sum::<f64>() / n - (data.iter().sum::<f64>() / n).powi(2);
+        assert!((naive - 22.5).abs() > 1.0);
+
+        let stats = Stats::new(data);
+        assert_eq!(stats.mean, 1e9 + 10.0);
+        assert_eq!(stats.variance, 22.5);
+    }
+
+    #[test]
+    fn test_rank() {
+        let stats = Stats::new(vec![10.0, 20.0, 30.0, 40.0, 50.0]);
+
+        assert_eq!(stats.rank(5.0), 0.0);
+        assert_eq!(stats.rank(10.0), 0.0);
+        assert_eq!(stats.rank(30.0), 0.5);
+        assert_eq!(stats.rank(35.0), 0.625);
+        assert_eq!(stats.rank(50.0), 1.0);
+        assert_eq!(stats.rank(99.0), 1.0);
+
+        // Round-trips through quantile
+        for q in [0.1, 0.33, 0.9] {
+            assert!((stats.rank(stats.quantile(q)) - q).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_rank_ties() {
+        let stats = Stats::new(vec![1.0, 2.0, 2.0, 2.0, 3.0]);
+        assert_eq!(stats.rank(2.0), 0.5);
+
+        assert_eq!(Stats::new(vec![7.0]).rank(7.0), 0.5);
+        assert!(Stats::new(vec![]).rank(1.0).is_nan());
+    }
+
+    #[test]
+    fn test_inverse_normal_cdf() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        assert!(close(inverse_normal_cdf(0.975), 1.959964));
+        assert!(close(inverse_normal_cdf(0.995), 2.575829));
+        assert!(close(inverse_normal_cdf(0.95), 1.644854));
+        assert!(close(inverse_normal_cdf(0.01), -2.326348));
+        assert_eq!(inverse_normal_cdf(0.5), 0.0);
+        assert_eq!(inverse_normal_cdf(1.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_student_t_quantile() {
+        // Reference values from standard t tables
+        let close = |a: f64, b: f64, tol: f64| (a - b).abs() < tol;
+        assert!(close(student_t_quantile(0.975, 1.0), 12.706, 1e-3));
+        assert!(close(student_t_quantile(0.975, 2.0), 4.303, 1e-3));
+        assert!(close(student_t_quantile(0.975, 5.0), 2.571, 2e-3));
+        assert!(close(student_t_quantile(0.975, 10.0), 2.228, 1e-3));
+        assert!(close(student_t_quantile(0.995, 30.0), 2.750, 1e-3));
+        assert!(close(student_t_quantile(0.975, 1e6), 1.960, 1e-3));
+    }
+
+    #[test]
+    fn test_mean_ci() {
+        // mean 3, sample sd sqrt(2.5), t(0.975, 4) = 2.776
+        let stats = Stats::new(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let (low, high) = stats.mean_ci(0.95);
+        let half_width = 2.776 * 2.5f64.sqrt() / 5f64.sqrt();
+        assert!((low - (3.0 - half_width)).abs() < 1e-2);
+        assert!((high - (3.0 + half_width)).abs() < 1e-2);
+
+        // Higher confidence gives a wider interval
+        let (low_99, high_99) = stats.mean_ci(0.99);
+        assert!(low_99 < low && high_99 > high);
+
+        assert!(Stats::new(vec![1.0]).mean_ci(0.95).0.is_nan());
+    }
+
+    #[test]
+    fn test_bootstrap_ci() {
+        let stats = Stats::new((1..=200).map(f64::from).collect());
+        let median = |s: &Stats| s.quantile(0.5);
+
+        let (low, high) = stats.bootstrap_ci(0.95, 500, 42, median);
+        assert!(low < 100.5 && 100.5 < high);
+        assert!(low > 80.0 && high < 121.0);
+
+        // Same seed, same interval; wider at higher confidence
+        assert_eq!(stats.bootstrap_ci(0.95, 500, 42, median), (low, high));
+        let (low_99, high_99) = stats.bootstrap_ci(0.99, 500, 42, median);
+        assert!(low_99 <= low && high_99 >= high);
+    }
+
+    #[test]
+    fn test_bootstrap_ci_constant() {
+        let stats = Stats::new(vec![5.0; 20]);
+        assert_eq!(stats.bootstrap_ci(0.95, 50, 1, |s| s.mean), (5.0, 5.0));
+        assert!(
+            Stats::new(vec![])
+                .bootstrap_ci(0.95, 50, 1, |s| s.mean)
+                .0
+                .is_nan()
+        );
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        assert!(close(normal_cdf(0.0), 0.5));
+        assert!(close(normal_cdf(1.959964), 0.975));
+        assert!(close(normal_cdf(-1.0), 0.158655));
+        assert!(close(normal_cdf(inverse_normal_cdf(0.3)), 0.3));
+    }
+
+    #[test]
+    fn test_mann_whitney_shifted() {
+        let a = Stats::new((0..50).map(|i| f64::from(i) + 10.0).collect());
+        let b = Stats::new((0..50).map(f64::from).collect());
+        let result = mann_whitney(&a, &b);
+
+        // a = 10..59 vs b = 0..49: each x in 10..49 beats x values of b and ties one
+        // (sum 1180 + 40 halves), and each x in 50..59 beats all 50
+        assert_eq!(result.u, 1180.0 + 20.0 + 500.0);
+        assert!(result.z > 0.0);
+        assert!(result.p_value < 0.05);
+    }
+
+    #[test]
+    fn test_mann_whitney_same_distribution() {
+        let a = Stats::new((0..40).map(|i| f64::from(i * 2)).collect());
+        let b = Stats::new((0..40).map(|i| f64::from(i * 2 + 1)).collect());
+        let result = mann_whitney(&a, &b);
+
+        assert!(result.p_value > 0.5);
+    }
+
+    #[test]
+    fn test_mann_whitney_ties() {
+        // Every pair ties, so U is half of n1·n2 and there's no evidence of a difference
+        let a = Stats::new(vec![3.0; 5]);
+        let b = Stats::new(vec![3.0; 7]);
+        let result = mann_whitney(&a, &b);
+        assert_eq!(result.u, 17.5);
+        assert_eq!(result.p_value, 1.0);
+
+        // Classic small example with one tie between samples
+        let a = Stats::new(vec![1.0, 2.0, 4.0]);
+        let b = Stats::new(vec![3.0, 4.0, 5.0, 6.0]);
+        // a > b pairs: none for 1, 2; 4 beats 3 and ties 4 -> 1.5
+        assert_eq!(mann_whitney(&a, &b).u, 1.5);
+    }
+
+    #[test]
+    fn test_ecdf() {
+        let stats = Stats::new(vec![3.0, 1.0, 2.0, 2.0]);
+        assert_eq!(
+            stats.ecdf(),
+            vec![
+                (1.0, 0.0),
+                (1.0, 0.25),
+                (2.0, 0.25),
+                (2.0, 0.5),
+                (2.0, 0.5),
+                (2.0, 0.75),
+                (3.0, 0.75),
+                (3.0, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_drops_nan() {
+        let stats = Stats::new(vec![3.0, f64::NAN, 1.0, 2.0]);
+
+        assert_eq!(stats.n, 3);
+        assert_eq!(stats.non_finite, 1);
+        assert_eq!(stats.data, vec![1.0, 2.0, 3.0]);
+        assert_eq!(stats.mean, 2.0);
+    }
+
+    #[test]
+    fn test_stats_drops_infinities() {
+        let stats = Stats::new(vec![f64::INFINITY, 1.0, f64::NEG_INFINITY, 2.0]);
+
+        assert_eq!(stats.n, 2);
+        assert_eq!(stats.non_finite, 2);
+        assert_eq!(stats.sum, 3.0);
+        assert_eq!(stats.quantile(1.0), 2.0);
+    }
+
+    #[test]
+    fn test_stats_all_non_finite() {
+        let stats = Stats::new(vec![f64::NAN, f64::INFINITY]);
+
+        assert_eq!(stats.n, 0);
+        assert_eq!(stats.non_finite, 2);
+        assert!(stats.quantile(0.5).is_nan());
+    }
+
+    #[test]
+    fn test_stats_finite_input_counts_nothing() {
+        let stats = Stats::new(vec![1.0, 2.0]);
+        assert_eq!(stats.non_finite, 0);
+    }
+
     #[test]
     fn test_stats_large_range() {
         let data = vec![1.0, 1000.0, 1000000.0];
@@ -5,7 +5,8 @@ pub enum Unit {
     // Time units
     #[value(name = "ns")]
     Nanoseconds,
-    #[value(name = "us")]
+    // Also spelled with the micro sign or Greek mu, as it's displayed
+    #[value(name = "us", alias = "µs", alias = "μs")]
     Microseconds,
     #[value(name = "ms")]
     Milliseconds,
@@ -37,6 +38,18 @@ pub enum Unit {
     Tebibytes,
     #[value(name = "PiB")]
     Pebibytes,
+
+    // Bit units (decimal), for network throughput and the like
+    #[value(name = "b")]
+    Bits,
+    #[value(name = "Kb")]
+    Kilobits,
+    #[value(name = "Mb")]
+    Megabits,
+    #[value(name = "Gb")]
+    Gigabits,
+    #[value(name = "Tb")]
+    Terabits,
 }
 
 impl Unit {
@@ -61,6 +74,51 @@ impl Unit {
             Self::Gibibytes => 1024.0_f64.powi(3),
             Self::Tebibytes => 1024.0_f64.powi(4),
             Self::Pebibytes => 1024.0_f64.powi(5),
+
+            // Bits: base unit is bits, a separate dimension from bytes (no 8x conversion)
+            Self::Bits => 1.0,
+            Self::Kilobits => 1e3,
+            Self::Megabits => 1e6,
+            Self::Gigabits => 1e9,
+            Self::Terabits => 1e12,
+        }
+    }
+
+    /// Short suffix used when displaying values in this unit
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "ns",
+            Self::Microseconds => "µs",
+            Self::Milliseconds => "ms",
+            Self::Seconds => "s",
+            Self::Bytes => "B",
+            Self::Kilobytes => "KB",
+            Self::Megabytes => "MB",
+            Self::Gigabytes => "GB",
+            Self::Terabytes => "TB",
+            Self::Petabytes => "PB",
+            Self::Kibibytes => "KiB",
+            Self::Mebibytes => "MiB",
+            Self::Gibibytes => "GiB",
+            Self::Tebibytes => "TiB",
+            Self::Pebibytes => "PiB",
+            Self::Bits => "b",
+            Self::Kilobits => "Kb",
+            Self::Megabits => "Mb",
+            Self::Gigabits => "Gb",
+            Self::Terabits => "Tb",
+        }
+    }
+
+    /// Recognizes a unit suffix written after a number in the input, e.g. "ms" in "12ms".
+    /// Accepts both "us" and "µs" (micro sign or Greek mu) for microseconds.
+    pub fn from_suffix(suffix: &str) -> Option<Unit> {
+        match suffix {
+            "us" | "µs" | "μs" => Some(Self::Microseconds),
+            _ => <Unit as clap::ValueEnum>::value_variants()
+                .iter()
+                .find(|unit| unit.label() == suffix)
+                .copied(),
         }
     }
 
@@ -70,9 +128,25 @@ impl Unit {
             Self::Nanoseconds | Self::Microseconds | Self::Milliseconds | Self::Seconds => {
                 Format::Time
             }
+            Self::Bits | Self::Kilobits | Self::Megabits | Self::Gigabits | Self::Terabits => {
+                Format::Bits
+            }
             _ => Format::Bytes,
         }
     }
+
+    /// Output format matching how values in this unit were written: like `default_format`,
+    /// but decimal byte units (KB, MB...) keep decimal prefixes instead of switching to KiB/MiB
+    pub fn preferred_format(&self) -> Format {
+        match self {
+            Self::Kilobytes
+            | Self::Megabytes
+            | Self::Gigabytes
+            | Self::Terabytes
+            | Self::Petabytes => Format::BytesSi,
+            _ => self.default_format(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -106,6 +180,23 @@ mod tests {
         assert_eq!(Unit::Pebibytes.scale(), 1024.0_f64.powi(5));
     }
 
+    #[test]
+    fn test_bit_unit_scales() {
+        assert_eq!(Unit::Bits.scale(), 1.0);
+        assert_eq!(Unit::Kilobits.scale(), 1e3);
+        assert_eq!(Unit::Megabits.scale(), 1e6);
+        assert_eq!(Unit::Gigabits.scale(), 1e9);
+        assert_eq!(Unit::Terabits.scale(), 1e12);
+    }
+
+    #[test]
+    fn test_bit_unit_default_format() {
+        assert!(matches!(Unit::Bits.default_format(), Format::Bits));
+        assert!(matches!(Unit::Megabits.default_format(), Format::Bits));
+        assert!(matches!(Unit::Gigabits.preferred_format(), Format::Bits));
+        assert_eq!(Unit::Megabits.label(), "Mb");
+    }
+
     #[test]
     fn test_time_unit_default_format() {
         assert!(matches!(Unit::Nanoseconds.default_format(), Format::Time));
@@ -123,6 +214,54 @@ mod tests {
         assert!(matches!(Unit::Mebibytes.default_format(), Format::Bytes));
     }
 
+    #[test]
+    fn test_unit_labels() {
+        assert_eq!(Unit::Microseconds.label(), "µs");
+        assert_eq!(Unit::Seconds.label(), "s");
+        assert_eq!(Unit::Kilobytes.label(), "KB");
+        assert_eq!(Unit::Mebibytes.label(), "MiB");
+    }
+
+    #[test]
+    fn test_from_suffix() {
+        assert!(matches!(Unit::from_suffix("ms"), Some(Unit::Milliseconds)));
+        assert!(matches!(Unit::from_suffix("us"), Some(Unit::Microseconds)));
+        assert!(matches!(Unit::from_suffix("µs"), Some(Unit::Microseconds)));
+        assert!(matches!(Unit::from_suffix("μs"), Some(Unit::Microseconds)));
+        assert!(matches!(Unit::from_suffix("MiB"), Some(Unit::Mebibytes)));
+        assert!(matches!(Unit::from_suffix("Mb"), Some(Unit::Megabits)));
+        assert!(Unit::from_suffix("mb").is_none());
+        assert!(Unit::from_suffix("").is_none());
+    }
+
+    #[test]
+    fn test_micro_sign_unit_names() {
+        use clap::ValueEnum;
+
+        for name in ["us", "µs", "μs"] {
+            let unit = Unit::from_str(name, false).unwrap();
+            assert_eq!(unit.scale(), 1e3, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_preferred_format() {
+        assert!(matches!(
+            Unit::Kilobytes.preferred_format(),
+            Format::BytesSi
+        ));
+        assert!(matches!(
+            Unit::Petabytes.preferred_format(),
+            Format::BytesSi
+        ));
+        assert!(matches!(Unit::Kibibytes.preferred_format(), Format::Bytes));
+        assert!(matches!(Unit::Bytes.preferred_format(), Format::Bytes));
+        assert!(matches!(
+            Unit::Milliseconds.preferred_format(),
+            Format::Time
+        ));
+    }
+
     #[test]
     fn test_conversion_examples() {
         // 5 microseconds = 5000 nanoseconds
@@ -0,0 +1,828 @@
+use std::io::Write;
+use std::process::{Command, Output};
+use tempfile::NamedTempFile;
+
+fn write_input(contents: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(contents.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file
+}
+
+fn disty(args: &[&str], input: &NamedTempFile) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_disty"))
+        .args(args)
+        .arg(input.path())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_nan_ignored_by_default() {
+    let input = write_input("1\nnan\n2\n");
+    let output = disty(&["--no-plot"], &input);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ignored 1 non-finite"));
+}
+
+#[test]
+fn test_fail_on_nan() {
+    let input = write_input("1\nnan\n2\n");
+    let output = disty(&["--no-plot", "--fail-on-nan"], &input);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_fail_on_nan_with_finite_input() {
+    let input = write_input("1\n2\n");
+    let output = disty(&["--no-plot", "--fail-on-nan"], &input);
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_only_prints_raw_value() {
+    let input = write_input("1\n2\n3\n4\n");
+    let output = disty(&["--only", "median", "-u", "ms"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2500000\n");
+}
+
+#[test]
+fn test_only_unknown_statistic() {
+    let input = write_input("1\n2\n");
+    let output = disty(&["--only", "average"], &input);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_rank_prints_percentile() {
+    let input = write_input("100\n200\n300\n400\n500\n");
+    let output = disty(&["--rank", "350", "-u", "ms"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "62.5\n");
+}
+
+#[test]
+fn test_sample_reports_effective_n() {
+    let input = write_input("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n");
+    let output = disty(&["--sample", "4", "--seed", "1", "--only", "n"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "4\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("sampled 4 of 10 values"));
+    assert!(!stderr.contains("seed:"));
+}
+
+#[test]
+fn test_sample_reproducible_with_seed() {
+    let input = write_input("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n");
+    let run = || disty(&["--sample", "3", "--seed", "42", "--only", "sum"], &input).stdout;
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_sample_prints_random_seed() {
+    let input = write_input("1\n2\n3\n");
+    let output = disty(&["--sample", "2", "--only", "n"], &input);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("seed: "));
+}
+
+#[test]
+fn test_decimal_comma() {
+    let input = write_input("1,5\n2,5\n-1.000,0\n");
+    let output = disty(&["--decimal-comma", "--only", "sum"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "-996\n");
+}
+
+#[test]
+fn test_decimal_comma_rejects_comma_delimited_column() {
+    let input = write_input("a,1,5\n");
+    let output = disty(&["--decimal-comma", "--column", "2"], &input);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_follow_requires_input_files() {
+    let output = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .arg("--follow")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_no_summary_prints_only_plot() {
+    let input = write_input("1\n2\n2\n3\n5\n");
+    let output = disty(&["--no-summary", "--color", "never"], &input);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("median"));
+    assert!(!stdout.starts_with('\n'));
+    // Just the chart: 40 pixel rows are 10 lines of braille, plus the axis labels
+    assert!(stdout.lines().count() >= 10);
+}
+
+#[test]
+fn test_no_summary_with_no_plot_prints_nothing() {
+    let input = write_input("1\n2\n3\n");
+    let output = disty(&["--no-summary", "--no-plot"], &input);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_histogram_out_writes_csv() {
+    let input = write_input("0\n1\n2.5\n4.9\n5\n7.5\n9\n10\n");
+    let out = NamedTempFile::new().unwrap();
+    let output = disty(
+        &[
+            "--no-plot",
+            "--bins",
+            "4",
+            "--histogram-out",
+            out.path().to_str().unwrap(),
+        ],
+        &input,
+    );
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("median"));
+    assert_eq!(
+        std::fs::read_to_string(out.path()).unwrap(),
+        "bin_center,count\n1.25,2\n3.75,2\n6.25,1\n8.75,3\n"
+    );
+}
+
+#[test]
+fn test_min_count_rejects_too_few_values() {
+    let input = write_input("1\n2\n");
+    let output = disty(&["--no-plot", "--min-count", "3"], &input);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("read 2 values"));
+    assert!(stderr.contains("at least 3"));
+}
+
+#[test]
+fn test_min_count_rejects_empty_input() {
+    let input = write_input("");
+    let output = disty(&["--min-count", "1"], &input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_min_count_met() {
+    let input = write_input("1\n2\n3\n");
+    let output = disty(&["--no-plot", "--min-count", "3"], &input);
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_dump_sorted_prints_processed_values() {
+    let input = write_input("id,ms\na,3\nb,1.5\nc,nan\nd,2\n");
+    let output = disty(
+        &[
+            "--dump",
+            "sorted",
+            "--column",
+            "2",
+            "--skip-header",
+            "1",
+            "-u",
+            "ms",
+        ],
+        &input,
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "1500000\n2000000\n3000000\n"
+    );
+}
+
+#[test]
+fn test_head_and_last_select_values() {
+    let input = write_input("# values\n1\n2\n3\n4\n5\n");
+    let output = disty(&["--dump", "sorted", "--head", "3", "--last", "2"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n3\n");
+}
+
+#[test]
+fn test_last_keeps_trailing_values() {
+    let input = write_input("5\n4\n3\n2\n1\n");
+    let output = disty(&["--dump", "sorted", "--last", "2"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n2\n");
+}
+
+#[test]
+fn test_with_index_reports_extreme_lines() {
+    let input = write_input("# latencies\n5\n9\n\n1\n9\n");
+    let output = disty(&["--no-plot", "--with-index"], &input);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let row = |label: &str| {
+        stdout
+            .lines()
+            .find_map(|line| {
+                line.split_once(label)
+                    .map(|(_, rest)| rest.trim().to_string())
+            })
+            .unwrap()
+    };
+    assert_eq!(row("min at"), "line 5");
+    // The first of the tied maxima
+    assert_eq!(row("max at"), "line 3");
+}
+
+#[test]
+fn test_relative_to_uses_input_unit() {
+    let input = write_input("50\n100\n150\n");
+    let output = disty(&["--no-plot", "-u", "ms", "--relative-to", "100"], &input);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1.00×"));
+    assert!(stdout.contains("1.50×"));
+}
+
+#[test]
+fn test_merge_stdin_pools_file_and_piped_values() {
+    use std::process::Stdio;
+
+    let input = write_input("1\n2\n");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .args(["--only", "sum", "--merge-stdin"])
+        .arg(input.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"3\n4\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "10");
+}
+
+#[test]
+fn test_merge_stdin_requires_inputs() {
+    let output = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .arg("--merge-stdin")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_quantile_method_changes_percentiles() {
+    let input = write_input("1\n2\n3\n4\n");
+    let median = |method: &str| {
+        let output = disty(&["--only", "median", "--quantile-method", method], &input);
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    assert_eq!(median("linear"), "2.5");
+    assert_eq!(median("lower"), "2");
+    assert_eq!(median("higher"), "3");
+    assert_eq!(median("nearest"), "3");
+}
+
+#[test]
+fn test_exclude_zeros_defines_geometric_mean() {
+    let input = write_input("0\n2\n0\n8\n");
+
+    let output = disty(&["--only", "gmean"], &input);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "NaN");
+
+    let output = disty(&["--only", "gmean", "--exclude-zeros"], &input);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("excluded 2 zero values"));
+}
+
+#[test]
+fn test_geomean_ignore_nonpositive() {
+    let input = write_input("-1\n0\n2\n8\n");
+
+    let output = disty(&["--only", "gmean", "--geomean-ignore-nonpositive"], &input);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4");
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("excluded 2 non-positive values from gmean and gsd")
+    );
+}
+
+#[test]
+fn test_geomean_ignore_nonpositive_after_clip() {
+    let input = write_input("-3\n0\n2\n8\n100\n");
+
+    let output = disty(
+        &[
+            "--only",
+            "gmean",
+            "--geomean-ignore-nonpositive",
+            "--clip=-5,50",
+        ],
+        &input,
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("excluded 2 non-positive values"));
+}
+
+#[test]
+fn test_geomean_always_prints_nan_in_any_format() {
+    let input = write_input("0\n1\n2\n5\n");
+
+    for format in [["-u", "ms"], ["-f", "hex"]] {
+        let mut args = vec!["--geomean-always", "-q"];
+        args.extend(format);
+        let output = disty(&args, &input);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let gmean = stdout.lines().find(|l| l.contains("gmean")).unwrap();
+        assert_eq!(gmean.split_whitespace().nth(1), Some("NaN"), "{gmean}");
+    }
+}
+
+#[test]
+fn test_geomean_always_shows_undefined_rows() {
+    let input = write_input("0\n2\n8\n");
+
+    let output = disty(&["--no-plot"], &input);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("gmean"));
+
+    let output = disty(&["--no-plot", "--geomean-always"], &input);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|l| l.contains("gmean") && l.contains("NaN"))
+    );
+    assert!(stdout.contains("gsd"));
+}
+
+#[test]
+fn test_token_split_reads_one_line_of_values() {
+    let input = write_input("0x10 0x20\t0x30 0x40\n");
+    let output = disty(&["--only", "sum", "--token-split"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "160");
+}
+
+#[test]
+fn test_kde_out_writes_points() {
+    let input = write_input("1\n2\n2\n3\n5\n");
+    let out = NamedTempFile::new().unwrap();
+    let output = disty(
+        &[
+            "--no-plot",
+            "--kde-out",
+            out.path().to_str().unwrap(),
+            "--kde-points",
+            "50",
+        ],
+        &input,
+    );
+
+    assert!(output.status.success());
+    let csv = std::fs::read_to_string(out.path()).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("x,density"));
+    let points: Vec<(f64, f64)> = lines
+        .map(|line| {
+            let (x, y) = line.split_once(',').unwrap();
+            (x.parse().unwrap(), y.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(points.len(), 50);
+    assert!(points.windows(2).all(|w| w[0].0 < w[1].0));
+    assert!(points.iter().all(|&(_, y)| y >= 0.0));
+}
+
+#[test]
+fn test_log_bins_writes_decades() {
+    let input = write_input("3\n40\n45\n2500\n");
+    let out = NamedTempFile::new().unwrap();
+    let output = disty(
+        &[
+            "--no-plot",
+            "--log-bins",
+            "--histogram-out",
+            out.path().to_str().unwrap(),
+        ],
+        &input,
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(out.path()).unwrap(),
+        "bin_lower,bin_upper,count\n1,10,1\n10,100,2\n100,1000,0\n1000,10000,1\n"
+    );
+}
+
+#[test]
+fn test_log_bins_rejects_non_positive() {
+    let input = write_input("0\n1\n2\n");
+    let out = NamedTempFile::new().unwrap();
+    let output = disty(
+        &[
+            "--log-bins",
+            "--histogram-out",
+            out.path().to_str().unwrap(),
+        ],
+        &input,
+    );
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs positive values"));
+}
+
+#[test]
+fn test_count_only_reports_totals() {
+    let input = write_input("# sizes\n1\n2\nnan\n4.5\n");
+    let output = disty(&["--count-only", "--output-format", "markdown"], &input);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("| n | 3 |"), "{stdout}");
+    assert!(stdout.contains("| sum | 7.50 |"), "{stdout}");
+    assert!(stdout.contains("| mean | 2.50 |"), "{stdout}");
+    assert!(!stdout.contains("median"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ignored 1 non-finite"));
+}
+
+#[test]
+fn test_count_only_respects_fail_on_nan() {
+    let input = write_input("1\nnan\n");
+    let output = disty(&["--count-only", "--fail-on-nan"], &input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_grep_keeps_matching_lines() {
+    let input = write_input("INFO took 12\nDEBUG took 9000\nINFO took 30\n");
+    let output = disty(
+        &[
+            "--only",
+            "sum",
+            "--grep",
+            "^INFO",
+            "--column",
+            "3",
+            "--delimiter",
+            " ",
+        ],
+        &input,
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "42");
+
+    let output = disty(
+        &[
+            "--only",
+            "sum",
+            "--grep",
+            "^INFO",
+            "--grep-invert",
+            "--column",
+            "3",
+            "--delimiter",
+            " ",
+        ],
+        &input,
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "9000");
+}
+
+#[test]
+fn test_grep_rejects_invalid_pattern() {
+    let input = write_input("1\n");
+    let output = disty(&["--grep", "("], &input);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_stats_selects_table_rows() {
+    let input = write_input("1\n2\n3\n4\n5\n");
+    let output = disty(&["--no-plot", "--stats", "median,n"], &input);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let labels: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(labels, ["median", "n"]);
+}
+
+#[test]
+fn test_markers_print_legend() {
+    let input = write_input("1\n2\n2\n3\n5\n");
+    let output = disty(&["--no-summary", "--markers=median,max"], &input);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().last().unwrap(),
+        "markers: median = 2.00, max = 5.00"
+    );
+}
+
+#[test]
+fn test_time_unit_with_byte_format_needs_force() {
+    let input = write_input("1\n2\n3\n");
+    let output = disty(&["--no-plot", "--unit", "ms", "--fmt", "bytes"], &input);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+
+    let output = disty(
+        &["--no-plot", "--unit", "ms", "--fmt", "bytes", "--force"],
+        &input,
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_fractional_byte_counts_warn() {
+    let input = write_input("0.25\n0.5\n0.75\n");
+    let output = disty(&["--no-plot", "--unit", "B"], &input);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("whole number of bytes"));
+
+    let output = disty(&["--no-plot", "--unit", "B", "--force"], &input);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_cumulative_totals_in_input_order() {
+    let input = write_input("5\n1\n-2\n4\n");
+    let output = disty(&["--cumulative", "--dump", "sorted"], &input);
+
+    assert!(output.status.success());
+    // Running totals 5, 6, 4, 8
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "4\n5\n6\n8\n");
+
+    let output = disty(&["--cumulative", "--last", "2", "--only", "max"], &input);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "2");
+}
+
+#[test]
+fn test_file_input_stats() {
+    // 1..=1000, large enough to be split across the parser's worker threads
+    let contents: String = (1..=1000).map(|i| format!("{}\n", i)).collect();
+    let input = write_input(&contents);
+
+    for (statistic, expected) in [
+        ("n", "1000"),
+        ("sum", "500500"),
+        ("mean", "500.5"),
+        ("median", "500.5"),
+        ("min", "1"),
+        ("max", "1000"),
+    ] {
+        let output = disty(&["--only", statistic], &input);
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            expected,
+            "{statistic}"
+        );
+    }
+}
+
+#[test]
+fn test_pipe_path_input() {
+    use std::process::Stdio;
+
+    // /dev/stdin is a pipe here, like the path behind `<(cmd)`: it can't be mmapped or seeked
+    let mut child = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .args(["--only", "sum", "/dev/stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"3\n4\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "7");
+}
+
+#[test]
+fn test_align_decimal_points_by_default() {
+    let input = write_input("1\n2\n1000\n");
+    let row = |args: &[&str], label: &str| {
+        let output = disty(args, &input);
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with(label))
+            .unwrap()
+            .to_string()
+    };
+
+    let sum = row(&["--no-plot"], "sum");
+    let mean = row(&["--no-plot"], "mean");
+    assert_eq!(sum.find('.'), mean.find('.'));
+
+    let mean = row(&["--no-plot", "--align", "left"], "mean");
+    assert!(mean.starts_with("    mean  334.33"), "{mean}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fifo_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("values");
+    let made = Command::new("mkfifo").arg(&path).status().unwrap();
+    assert!(made.success());
+
+    let writer_path = path.clone();
+    let writer = std::thread::spawn(move || std::fs::write(writer_path, b"4\n5\n").unwrap());
+    let output = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .args(["--only", "sum"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    writer.join().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "9");
+}
+
+#[test]
+fn test_list_output_with_field_sep() {
+    let input = write_input("1\n2\n3\n");
+    let output = disty(
+        &[
+            "--output-format",
+            "list",
+            "--field-sep",
+            "=",
+            "--stats",
+            "n,mean",
+        ],
+        &input,
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "n=3\nmean=2.00\n");
+
+    let output = disty(&["--field-sep", "="], &input);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_baseline_filtered_like_inputs() {
+    let values: String = (1..=30).map(|i| format!("{}\n", i)).collect();
+    let input = write_input(&format!("{}{}", values, "0\n".repeat(30)));
+    let baseline = write_input(&format!("{}{}", values, "0\n".repeat(30)));
+    let baseline_path = baseline.path().to_str().unwrap();
+
+    let output = disty(
+        &["--no-plot", "--exclude-zeros", "--baseline", baseline_path],
+        &input,
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("vs baseline: no significant"), "{stdout}");
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("excluded 30 zero values from the baseline")
+    );
+
+    let output = disty(
+        &["--no-plot", "--clip=5,25", "--baseline", baseline_path],
+        &input,
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("vs baseline: no significant"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("clipped 39 baseline values"));
+}
+
+#[test]
+fn test_prefixed_literals_and_overflow_warning() {
+    let input = write_input("0x1.8p3\n0XC\n0B1100\n0x10000000000000000\n");
+    let output = disty(&["--only", "sum"], &input);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "36");
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("skipped 1 integer inputs too large for 64 bits")
+    );
+}
+
+#[test]
+fn test_column_skips_short_lines_on_stdin() {
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .args(["--only", "n", "--column", "2"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"a,1\nb\nc,3\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "2");
+}
+
+#[test]
+fn test_json_field_skips_missing_keys_on_stdin() {
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_disty"))
+        .args(["--only", "sum", "--json-field", "a"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"{\"a\":1}\n{\"b\":2}\n{\"a\":4}\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "5");
+}
+
+#[test]
+fn test_gzip_column_reads_like_uncompressed() {
+    use flate2::{Compression, write::GzEncoder};
+
+    let csv = "name,ms\na,1\nshort\nb,oops\nc,3\n";
+    let plain = write_input(csv);
+    let compressed = NamedTempFile::new().unwrap();
+    let mut encoder = GzEncoder::new(compressed.reopen().unwrap(), Compression::default());
+    encoder.write_all(csv.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    for input in [&plain, &compressed] {
+        let output = disty(&["--only", "sum", "--column", "2"], input);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4");
+    }
+}